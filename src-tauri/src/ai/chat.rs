@@ -0,0 +1,370 @@
+// Follow-up chat about a track. Each track (normalized "artist|title", the same key as its
+// song context and lyrics) has its own conversation, kept in song-chats.json. Every question
+// is sent with the track's cached song context and lyrics as background, so questions like
+// "what's the story behind the second verse" have something to go on.
+
+use super::provider::{AiFeature, ChatMessage, ChatRole, CompletionRequest};
+use crate::config::AiSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const CHATS_FILE_VERSION: u32 = 1;
+
+// Messages kept per conversation, and conversations kept; the oldest go first
+const MAX_MESSAGES: usize = 200;
+const MAX_CONVERSATIONS: usize = 200;
+
+// Earlier messages sent with each question, which bounds the size of a request
+const HISTORY_SENT: usize = 20;
+
+// Lyrics past this many characters are left out of the background
+const MAX_SEED_LYRICS_CHARS: usize = 6000;
+
+const MAX_QUESTION_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub content: String,
+    // Seconds since the epoch
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    // As shown in the player when the conversation was last used
+    pub artist: String,
+    pub title: String,
+    pub messages: Vec<ChatTurn>,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChatsFile {
+    version: u32,
+    // Track key -> conversation
+    conversations: BTreeMap<String, Conversation>,
+}
+
+pub struct ChatStore {
+    // None keeps conversations in memory only
+    path: Option<PathBuf>,
+    file: Mutex<ChatsFile>,
+}
+
+impl ChatStore {
+    pub fn load(path: PathBuf) -> Self {
+        let file = crate::persist::read_json::<ChatsFile>(&path).unwrap_or_default();
+        Self { path: Some(path), file: Mutex::new(file) }
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        Self { path: None, file: Mutex::new(ChatsFile::default()) }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Conversation> {
+        self.file.lock().unwrap().conversations.get(key).cloned()
+    }
+
+    // Add a question and its answer. They are saved together, once the answer has arrived,
+    // so a failed or cancelled request doesn't leave an unanswered question behind.
+    pub fn append(&self, key: &str, artist: &str, title: &str, question: ChatTurn, answer: ChatTurn) {
+        let mut file = self.file.lock().unwrap();
+        let conversation = file.conversations.entry(key.to_string()).or_default();
+        conversation.artist = artist.to_string();
+        conversation.title = title.to_string();
+        conversation.updated_at = answer.at;
+        conversation.messages.push(question);
+        conversation.messages.push(answer);
+        let excess = conversation.messages.len().saturating_sub(MAX_MESSAGES);
+        conversation.messages.drain(..excess);
+
+        while file.conversations.len() > MAX_CONVERSATIONS {
+            let oldest = file
+                .conversations
+                .iter()
+                .min_by_key(|(_, conversation)| conversation.updated_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                file.conversations.remove(&oldest);
+            }
+        }
+
+        self.save(&mut file);
+    }
+
+    // Returns whether there was a conversation to clear
+    pub fn clear(&self, key: &str) -> bool {
+        let mut file = self.file.lock().unwrap();
+        let removed = file.conversations.remove(key).is_some();
+        if removed {
+            self.save(&mut file);
+        }
+        removed
+    }
+
+    fn save(&self, file: &mut ChatsFile) {
+        file.version = CHATS_FILE_VERSION;
+        if let Some(path) = &self.path {
+            if let Err(e) = crate::persist::write_json(path, &*file) {
+                log_error!("[Basitune] Failed to save song chats: {}", e);
+            }
+        }
+    }
+}
+
+// Background for the conversation: what the track is, and its song context and lyrics
+// when they are cached
+pub fn seed(ai: &AiSettings, artist: &str, title: &str, context: Option<&str>, lyrics: Option<&str>) -> String {
+    let language = ai.language.as_deref().unwrap_or(super::prompts::DEFAULT_LANGUAGE);
+    let mut seed = format!(
+        "You are answering follow-up questions about the song '{}' by {}. Keep answers brief and \
+        answer in {}. If you don't know something, say so rather than guessing.",
+        title.trim(),
+        artist.trim(),
+        language
+    );
+
+    if let Some(context) = context.map(str::trim).filter(|context| !context.is_empty()) {
+        seed.push_str("\n\nAbout the song:\n");
+        seed.push_str(context);
+    }
+    if let Some(lyrics) = lyrics.map(str::trim).filter(|lyrics| !lyrics.is_empty()) {
+        seed.push_str("\n\nLyrics:\n");
+        seed.extend(lyrics.chars().take(MAX_SEED_LYRICS_CHARS));
+        if lyrics.chars().count() > MAX_SEED_LYRICS_CHARS {
+            seed.push_str("\n[...]");
+        }
+    }
+    seed
+}
+
+// Request for `question`: the background first, then the most recent messages of the
+// conversation so far
+pub fn request(ai: &AiSettings, seed: String, conversation: Option<&Conversation>, question: &str) -> CompletionRequest {
+    let mut request = AiFeature::SongChat.request(ai, question.to_string());
+    let earlier = conversation.map(|c| c.messages.as_slice()).unwrap_or_default();
+    request.history = std::iter::once(ChatMessage { role: ChatRole::System, content: seed })
+        .chain(earlier[earlier.len().saturating_sub(HISTORY_SENT)..].iter().map(|turn| ChatMessage {
+            role: turn.role,
+            content: turn.content.clone(),
+        }))
+        .collect();
+    request
+}
+
+fn validate_question(question: &str) -> Result<&str, String> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err("Type a question first".to_string());
+    }
+    if question.chars().count() > MAX_QUESTION_CHARS {
+        return Err(format!("Questions can be at most {} characters", MAX_QUESTION_CHARS));
+    }
+    Ok(question)
+}
+
+// The conversation as Markdown, for export
+pub fn to_markdown(conversation: &Conversation) -> String {
+    let mut markdown = format!("# \"{}\" by {}\n", conversation.title, conversation.artist);
+    for turn in &conversation.messages {
+        let speaker = match turn.role {
+            ChatRole::User => "You",
+            ChatRole::Assistant | ChatRole::System => "AI",
+        };
+        markdown.push_str(&format!("\n**{}:** {}\n", speaker, turn.content.trim()));
+    }
+    markdown
+}
+
+// Name for an exported conversation, e.g. "basitune-chat-nirvana-lithium.md"
+fn export_file_name(artist: &str, title: &str) -> String {
+    let mut slug = String::new();
+    for c in format!("{} {}", artist, title).chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(80).collect();
+    format!("basitune-chat-{}.md", slug.trim_end_matches('-'))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn track_key(artist: &str, title: &str) -> String {
+    use crate::utils::normalize_string;
+    format!("{}|{}", normalize_string(artist), normalize_string(title))
+}
+
+// Ask a question about a track. Resolves to the answer, which is saved with the question;
+// with a request_id the answer also streams in as ai-chunk events.
+#[tauri::command]
+pub async fn chat_about_song(
+    title: String,
+    artist: String,
+    question: String,
+    request_id: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<ChatTurn, String> {
+    use crate::cache::{CacheCategory, ContentCache};
+
+    let question = validate_question(&question)?.to_string();
+    let settings = crate::config::load_config(&app).ai;
+    let key = track_key(&artist, &title);
+
+    // Background from the cache only; a chat doesn't fetch anything else on its own
+    let cache = app.state::<ContentCache>();
+    let context_key = super::prompts::song_context(&settings, &artist, &title, None, key.clone()).cache_key;
+    // Peeked so that a chat turn isn't counted as a cache hit or miss
+    let context = cache.peek(CacheCategory::SongContext, &context_key).map(|entry| entry.value);
+    let lyrics = cache.peek(CacheCategory::Lyrics, &key).map(|entry| entry.value);
+    let seed = seed(&settings, &artist, &title, context.as_deref(), lyrics.as_deref());
+
+    let store = app.state::<ChatStore>();
+    let asked = ChatTurn { role: ChatRole::User, content: question.clone(), at: now() };
+    let request = request(&settings, seed, store.get(&key).as_ref(), &question);
+
+    super::usage::check_budget(&app, &settings)?;
+    let provider = super::provider::for_app(&app, &settings)?;
+    let completion = super::streaming::complete(&app, request_id.as_deref(), provider.as_ref(), request).await?;
+    super::usage::record(&app, AiFeature::SongChat, provider.name(), &settings, &completion);
+
+    let answer = ChatTurn { role: ChatRole::Assistant, content: completion.text.trim().to_string(), at: now() };
+    store.append(&key, &artist, &title, asked, answer.clone());
+    Ok(answer)
+}
+
+// The conversation about a track so far; empty if there isn't one
+#[tauri::command]
+pub fn get_song_chat(title: String, artist: String, app: tauri::AppHandle) -> Conversation {
+    app.state::<ChatStore>()
+        .get(&track_key(&artist, &title))
+        .unwrap_or(Conversation { artist, title, ..Default::default() })
+}
+
+#[tauri::command]
+pub fn clear_song_chat(title: String, artist: String, app: tauri::AppHandle) -> bool {
+    let cleared = app.state::<ChatStore>().clear(&track_key(&artist, &title));
+    if cleared {
+        log_info!("[Basitune] Cleared the chat about {} - {}", artist, title);
+    }
+    cleared
+}
+
+// Write the conversation about a track to a Markdown file in the Downloads folder. The
+// page can't choose the path, since it runs on a remote origin. Returns the path written.
+#[tauri::command]
+pub fn export_song_chat(title: String, artist: String, app: tauri::AppHandle) -> Result<String, String> {
+    let conversation = app
+        .state::<ChatStore>()
+        .get(&track_key(&artist, &title))
+        .filter(|conversation| !conversation.messages.is_empty())
+        .ok_or_else(|| "There is no chat about this song to export".to_string())?;
+
+    let path = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to find the Downloads folder: {}", e))?
+        .join(export_file_name(&conversation.artist, &conversation.title));
+
+    crate::persist::write_atomic(&path, to_markdown(&conversation).as_bytes())?;
+    log_info!("[Basitune] Exported song chat to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(role: ChatRole, content: &str, at: u64) -> ChatTurn {
+        ChatTurn { role, content: content.to_string(), at }
+    }
+
+    #[test]
+    fn questions_are_saved_with_their_answers() {
+        let store = ChatStore::in_memory();
+        store.append("nirvana|lithium", "Nirvana", "Lithium", turn(ChatRole::User, "Which album?", 1), turn(ChatRole::Assistant, "Nevermind.", 2));
+        store.append("nirvana|lithium", "Nirvana", "Lithium", turn(ChatRole::User, "When?", 3), turn(ChatRole::Assistant, "1991.", 4));
+
+        let conversation = store.get("nirvana|lithium").unwrap();
+        assert_eq!(conversation.messages.len(), 4);
+        assert_eq!(conversation.messages[3].content, "1991.");
+        assert_eq!(conversation.updated_at, 4);
+        assert!(store.get("nirvana|polly").is_none());
+
+        assert!(store.clear("nirvana|lithium"));
+        assert!(!store.clear("nirvana|lithium"));
+        assert!(store.get("nirvana|lithium").is_none());
+    }
+
+    #[test]
+    fn old_messages_and_conversations_are_dropped() {
+        let store = ChatStore::in_memory();
+        for i in 0..MAX_MESSAGES as u64 {
+            store.append("a|b", "A", "B", turn(ChatRole::User, &format!("q{}", i), i), turn(ChatRole::Assistant, "a", i));
+        }
+        let messages = store.get("a|b").unwrap().messages;
+        assert_eq!(messages.len(), MAX_MESSAGES);
+        assert_eq!(messages[0].content, format!("q{}", MAX_MESSAGES / 2));
+
+        for i in 0..MAX_CONVERSATIONS as u64 {
+            let key = format!("artist|{}", i);
+            store.append(&key, "Artist", "Song", turn(ChatRole::User, "q", 1000 + i), turn(ChatRole::Assistant, "a", 1000 + i));
+        }
+        // The least recently used conversation made room for the others
+        assert!(store.get("a|b").is_none());
+        assert!(store.get("artist|0").is_some());
+    }
+
+    #[test]
+    fn requests_carry_the_background_and_recent_history() {
+        let ai = AiSettings::default();
+        let lyrics = "la ".repeat(MAX_SEED_LYRICS_CHARS);
+        let seed = seed(&ai, "Nirvana", "Lithium", Some("A song about faith."), Some(&lyrics));
+        assert!(seed.contains("'Lithium' by Nirvana") && seed.contains("in English"));
+        assert!(seed.contains("About the song:\nA song about faith."));
+        assert!(seed.ends_with("[...]"));
+        assert!(!super::seed(&ai, "Nirvana", "Lithium", None, Some(" ")).contains("Lyrics"));
+
+        let mut conversation = Conversation::default();
+        for i in 0..30 {
+            let role = if i % 2 == 0 { ChatRole::User } else { ChatRole::Assistant };
+            conversation.messages.push(turn(role, &format!("m{}", i), i));
+        }
+        let request = request(&ai, "background".to_string(), Some(&conversation), "And the bridge?");
+        assert_eq!(request.prompt, "And the bridge?");
+        assert_eq!(request.history.len(), HISTORY_SENT + 1);
+        assert_eq!(request.history[0], ChatMessage { role: ChatRole::System, content: "background".to_string() });
+        assert_eq!(request.history[1].content, "m10");
+        assert_eq!(request.history[HISTORY_SENT].role, ChatRole::Assistant);
+    }
+
+    #[test]
+    fn questions_are_validated() {
+        assert_eq!(validate_question("  Which album?\n"), Ok("Which album?"));
+        assert!(validate_question(" ").is_err());
+        assert!(validate_question(&"?".repeat(MAX_QUESTION_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn conversations_export_as_markdown() {
+        let conversation = Conversation {
+            artist: "Nirvana".to_string(),
+            title: "Lithium".to_string(),
+            messages: vec![turn(ChatRole::User, "Which album?", 1), turn(ChatRole::Assistant, "Nevermind.\n", 2)],
+            updated_at: 2,
+        };
+        assert_eq!(to_markdown(&conversation), "# \"Lithium\" by Nirvana\n\n**You:** Which album?\n\n**AI:** Nevermind.\n");
+        assert_eq!(export_file_name("Sigur Rós", "Hoppípolla (Live)"), "basitune-chat-sigur-rós-hoppípolla-live.md");
+        assert_eq!(export_file_name("AC/DC", "T.N.T."), "basitune-chat-ac-dc-t-n-t.md");
+    }
+}
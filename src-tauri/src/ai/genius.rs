@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use scraper::{Html, Selector};
-use std::time::Duration;
+use crate::http::{send_with_retry, HttpError, RetryPolicy};
+
+// Bump when extract_raw_lyrics_from_html or the cleanup pipeline changes, so lyrics
+// scraped by an older version can be found and re-fetched
+pub const SCRAPER_VERSION: u32 = 1;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeniusSearchResponse {
@@ -31,12 +35,72 @@ pub struct GeniusArtist {
     pub name: String,
 }
 
+// Why a lyrics lookup failed. Only failures that will repeat on the next try are
+// cached; network errors and a missing token are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsMissReason {
+    NoResults,
+    NoMatch,
+    ParseFailed,
+}
+
+impl LyricsMissReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LyricsMissReason::NoResults => "no_results",
+            LyricsMissReason::NoMatch => "no_match",
+            LyricsMissReason::ParseFailed => "parse_failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Self::NoResults, Self::NoMatch, Self::ParseFailed]
+            .into_iter()
+            .find(|reason| reason.as_str() == s)
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            LyricsMissReason::NoResults => "No results found",
+            LyricsMissReason::NoMatch => "No matching song found",
+            LyricsMissReason::ParseFailed => "Could not extract lyrics from page",
+        }
+    }
+}
+
+// Remember a failed lookup so replays don't hit the Genius API again. Returns the error to report.
+fn record_lyrics_miss(cache: &crate::cache::ContentCache, cache_key: &str, reason: LyricsMissReason) -> String {
+    use crate::cache::{CacheCategory, EntryMetadata};
+
+    cache.insert(
+        CacheCategory::LyricsMiss,
+        cache_key.to_string(),
+        reason.as_str().to_string(),
+        EntryMetadata::new("genius").with_scraper_version(SCRAPER_VERSION),
+    );
+    reason.message().to_string()
+}
+
+// Identical lookups already running share one result, so a track change racing the sidebar
+// opening doesn't search and scrape Genius twice
 #[tauri::command]
 pub async fn get_lyrics(title: String, artist: String, app: tauri::AppHandle) -> Result<String, String> {
-    use crate::cache::{load_cache, update_lyrics};
-    use crate::utils::{normalize_string, clean_song_title, clean_lyrics_with_regex, match_score};
+    use crate::utils::normalize_string;
+
+    let cache_key = format!("{}|{}", normalize_string(&artist), normalize_string(&title));
+    crate::inflight::coalesce(&app, &inflight_key(&cache_key), &cache_key, lookup_lyrics(title, artist, app.clone())).await
+}
+
+fn inflight_key(cache_key: &str) -> String {
+    format!("{}:{}", crate::cache::CacheCategory::Lyrics.as_str(), cache_key)
+}
+
+async fn lookup_lyrics(title: String, artist: String, app: tauri::AppHandle) -> Result<String, String> {
+    use crate::cache::{CacheCategory, ContentCache, EntryMetadata};
+    use crate::utils::{normalize_string, clean_song_title, match_score};
     use crate::config::get_genius_token;
-    use crate::ai::openai::format_lyrics_with_ai;
+    use tauri::Manager;
 
     // Create cache key (normalized artist + title)
     let cache_key = format!("{}|{}", 
@@ -45,9 +109,9 @@ pub async fn get_lyrics(title: String, artist: String, app: tauri::AppHandle) ->
     );
     
     // Try to load from cache
-    let cache = load_cache(&app);
+    let cache = app.state::<ContentCache>();
     
-    if let Some(cached_lyrics) = cache.lyrics.get(&cache_key) {
+    if let Some(cached_lyrics) = cache.get(CacheCategory::Lyrics, &cache_key) {
         // Validate cached content isn't prose/literature
         // Check for common prose patterns that indicate non-lyrics content
         let is_prose = cached_lyrics.contains("he said") 
@@ -57,87 +121,142 @@ pub async fn get_lyrics(title: String, artist: String, app: tauri::AppHandle) ->
             || (cached_lyrics.contains(" the ") && cached_lyrics.len() > 500 && !cached_lyrics.contains("[Chorus]") && !cached_lyrics.contains("[Verse]"));
         
         if !is_prose {
-            return Ok(cached_lyrics.clone());
+            return Ok(cached_lyrics);
         }
         // If it looks like prose, fall through to re-fetch with filtering
     }
     
-    // Clean up title - remove extra info like (Acoustic), (Remastered), dates, etc. for better matching
-    let clean_title = clean_song_title(&title);
+    // A source the user picked by hand overrides search and match scoring. Pins and misses
+    // aren't lyrics, so reading them doesn't count toward the hit ratio.
+    let pinned_url = cache.peek(CacheCategory::LyricsPin, &cache_key).map(|entry| entry.value);
+    let is_pinned = pinned_url.is_some();
     
-    // Search Genius API for the song
-    let search_query = format!("{} {}", artist, clean_title);
-    let search_url = format!(
-        "https://api.genius.com/search?q={}",
-        urlencoding::encode(&search_query)
-    );
+    // A recent lookup already failed for this track - don't ask Genius again until it expires
+    if !is_pinned {
+        if let Some(miss) = cache.peek(CacheCategory::LyricsMiss, &cache_key) {
+            let message = LyricsMissReason::parse(&miss.value)
+                .map(|r| r.message())
+                .unwrap_or("No results found");
+            return Err(format!("No lyrics known: {}", message));
+        }
+    }
     
-    let client = reqwest::Client::builder()
-        .user_agent("Basitune/0.1.0 (https://github.com/basiphobe/Basitune)")
-        .timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = crate::http::client(&app)?;
     
-    let genius_token = get_genius_token(&app)
-        .ok_or_else(|| "Genius API token not configured. Please add it to config.json in your app data directory.".to_string())?;
-    let response = client
-        .get(&search_url)
-        .header("Authorization", format!("Bearer {}", genius_token))
-        .send()
-        .await
-        .map_err(|e| format!("Search request failed: {}", e))?;
+    let (song_url, best_score) = match pinned_url {
+        Some(url) => (url, None),
+        None => {
+            // Clean up title - remove extra info like (Acoustic), (Remastered), dates, etc. for better matching
+            let clean_title = clean_song_title(&title);
+            
+            // Search Genius API for the song
+            let search_query = format!("{} {}", artist, clean_title);
+            let search_url = format!(
+                "https://api.genius.com/search?q={}",
+                urlencoding::encode(&search_query)
+            );
+            
+            let genius_token = get_genius_token(&app)
+                .ok_or_else(|| "Genius API token not configured. Please add it in Settings.".to_string())?;
+            let search_result = search_genius(&client, &search_url, &genius_token).await?;
+            
+            // Find best matching result that is actually a song, using scored matching
+            let song_hits: Vec<_> = search_result
+                .response
+                .hits
+                .iter()
+                .filter(|hit| {
+                    // Only accept results that are explicitly songs
+                    // Reject if type is explicitly not "song" or if URL suggests non-song content
+                    match &hit.result.result_type {
+                        Some(t) => t == "song",
+                        None => {
+                            // If type is missing, check URL for red flags (literature, books, etc.)
+                            let url_lower = hit.result.url.to_lowercase();
+                            !url_lower.contains("/literature/") && !url_lower.contains("/books/")
+                        }
+                    }
+                })
+                .collect();
+            
+            if song_hits.is_empty() {
+                return Err(record_lyrics_miss(&cache, &cache_key, LyricsMissReason::NoResults));
+            }
+            
+            // Score each song hit and pick the best match above a threshold
+            let best = song_hits
+                .iter()
+                .map(|hit| {
+                    let score = match_score(
+                        &clean_title,
+                        &artist,
+                        &hit.result.title,
+                        &hit.result.primary_artist.name,
+                    );
+                    (*hit, score)
+                })
+                .filter(|(_, score)| *score >= 0.3)
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            
+            match best {
+                Some((hit, score)) => (hit.result.url.clone(), Some(score)),
+                None => return Err(record_lyrics_miss(&cache, &cache_key, LyricsMissReason::NoMatch)),
+            }
+        }
+    };
+    
+    // Scrape lyrics from the song page
+    let raw_lyrics = match fetch_raw_lyrics(&client, &song_url).await? {
+        Ok(raw_lyrics) => raw_lyrics,
+        // A pinned page that won't parse is reported as-is; the user chose it deliberately
+        Err(e) if is_pinned => return Err(e),
+        Err(e) => {
+            log_error!("[Basitune] {} ({})", e, song_url);
+            return Err(record_lyrics_miss(&cache, &cache_key, LyricsMissReason::ParseFailed));
+        }
+    };
+    
+    let (result, formatted_by) = format_scraped_lyrics(&raw_lyrics, &app).await;
     
-    if !response.status().is_success() {
-        return Err(format!("Genius API returned status: {}", response.status()));
+    // The user pinned or unpinned a page while this lookup was running. Their choice wins,
+    // so nothing fetched for the old source is cached; pin_lyrics_source stores its own.
+    let current_pin = cache.peek(CacheCategory::LyricsPin, &cache_key).map(|entry| entry.value);
+    if current_pin != pinned_url {
+        return Ok(cache.peek(CacheCategory::Lyrics, &cache_key).map(|entry| entry.value).unwrap_or(result));
     }
     
-    let search_result: GeniusSearchResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse search results: {}", e))?;
-    
-    // Find best matching result that is actually a song, using scored matching
-    let song_hits: Vec<_> = search_result
-        .response
-        .hits
-        .iter()
-        .filter(|hit| {
-            // Only accept results that are explicitly songs
-            // Reject if type is explicitly not "song" or if URL suggests non-song content
-            match &hit.result.result_type {
-                Some(t) => t == "song",
-                None => {
-                    // If type is missing, check URL for red flags (literature, books, etc.)
-                    let url_lower = hit.result.url.to_lowercase();
-                    !url_lower.contains("/literature/") && !url_lower.contains("/books/")
-                }
-            }
-        })
-        .collect();
-
-    // Score each song hit and pick the best match above a threshold
-    let best_match = song_hits
-        .iter()
-        .map(|hit| {
-            let score = match_score(
-                &clean_title,
-                &artist,
-                &hit.result.title,
-                &hit.result.primary_artist.name,
-            );
-            (*hit, score)
-        })
-        .filter(|(_, score)| *score >= 0.3)
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-        .map(|(hit, _)| hit)
-        .ok_or("No results found")?;
+    // Save to cache (flushed to disk in the background)
+    let metadata = EntryMetadata::new("genius")
+        .with_source(song_url, best_score)
+        .with_scraper_version(SCRAPER_VERSION)
+        .with_model(formatted_by);
+    cache.remove(CacheCategory::LyricsMiss, &cache_key);
+    cache.insert(CacheCategory::Lyrics, cache_key, result.clone(), metadata);
     
-    let song_url = &best_match.result.url;
+    Ok(result)
+}
+
+// Query the Genius search API, retrying rate limits and brief outages
+async fn search_genius(client: &reqwest::Client, search_url: &str, genius_token: &str) -> Result<GeniusSearchResponse, String> {
+    let build = || client.get(search_url).header("Authorization", format!("Bearer {}", genius_token));
+    let response = send_with_retry(&RetryPolicy::default(), "Genius search", build)
+        .await
+        .map_err(|e| match e {
+            e if e.is_unauthorized() => "Genius rejected the access token. Check it in Settings.".to_string(),
+            HttpError::Status { status, .. } => format!("Genius API returned status: {}", status),
+            HttpError::Network { .. } => format!("Search request failed: {}", e),
+        })?;
     
-    // Scrape lyrics from the song page
-    let lyrics_response = client
-        .get(song_url.as_str())
-        .send()
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse search results: {}", e))
+}
+
+// Download a Genius song page and extract its lyrics. The outer error is a failed
+// request; the inner one means the page was fetched but contained no lyrics.
+async fn fetch_raw_lyrics(client: &reqwest::Client, song_url: &str) -> Result<Result<String, String>, String> {
+    let lyrics_response = send_with_retry(&RetryPolicy::default(), "Lyrics page request", || client.get(song_url))
         .await
         .map_err(|e| format!("Failed to fetch lyrics page: {}", e))?;
     
@@ -146,30 +265,127 @@ pub async fn get_lyrics(title: String, artist: String, app: tauri::AppHandle) ->
         .await
         .map_err(|e| format!("Failed to read HTML: {}", e))?;
     
-    // Extract raw lyrics first (sync operation)
-    let raw_lyrics = extract_raw_lyrics_from_html(&html)?;
-    
-    // Try to clean with AI, but fall back to raw lyrics if AI refuses (copyright policy)
-    let result = match format_lyrics_with_ai(&raw_lyrics, &app).await {
-        Ok(cleaned) => {
+    Ok(extract_raw_lyrics_from_html(&html))
+}
+
+// Try to clean scraped lyrics with AI, but fall back to regex cleanup if AI refuses
+// (copyright policy) or fails. Also returns the model used, if any.
+async fn format_scraped_lyrics(raw_lyrics: &str, app: &tauri::AppHandle) -> (String, Option<String>) {
+    use crate::ai::openai::format_lyrics_with_ai;
+    use crate::utils::clean_lyrics_with_regex;
+
+    match format_lyrics_with_ai(raw_lyrics, app).await {
+        Ok(completion) => {
+            let cleaned = completion.text;
             // Check if AI refused to provide lyrics
             if cleaned.to_lowercase().contains("i can't provide") 
                 || cleaned.to_lowercase().contains("i cannot provide")
                 || cleaned.to_lowercase().contains("i'm sorry") {
-                clean_lyrics_with_regex(&raw_lyrics)
+                (clean_lyrics_with_regex(raw_lyrics), None)
             } else {
-                cleaned
+                (cleaned, Some(completion.model))
             }
         }
         Err(_) => {
-            clean_lyrics_with_regex(&raw_lyrics)
+            (clean_lyrics_with_regex(raw_lyrics), None)
         }
-    };
+    }
+}
+
+// Only Genius song pages may be pinned, since the URL is fetched on every refresh
+fn validate_genius_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| format!("Invalid lyrics URL: {}", url))?;
+    let host = parsed.host_str().unwrap_or_default();
     
-    // Save to cache atomically
-    update_lyrics(&app, cache_key, result.clone());
+    if parsed.scheme() == "https" && (host == "genius.com" || host.ends_with(".genius.com")) {
+        Ok(())
+    } else {
+        Err(format!("Not a Genius song page: {}", url))
+    }
+}
+
+// Fetch lyrics for a search result the user picked and pin that page to the track,
+// so later lookups and cache refreshes use it instead of automatic matching
+#[tauri::command]
+pub async fn pin_lyrics_source(title: String, artist: String, result: GeniusResult, app: tauri::AppHandle) -> Result<String, String> {
+    use crate::cache::{CacheCategory, ContentCache, EntryMetadata};
+    use crate::utils::{normalize_string, clean_song_title, match_score};
+    use tauri::Manager;
+
+    validate_genius_url(&result.url)?;
     
-    Ok(result)
+    let cache_key = format!("{}|{}", 
+        normalize_string(&artist), 
+        normalize_string(&title)
+    );
+    
+    let client = crate::http::client(&app)?;
+    
+    let raw_lyrics = fetch_raw_lyrics(&client, &result.url).await??;
+    let (lyrics, formatted_by) = format_scraped_lyrics(&raw_lyrics, &app).await;
+    
+    // Score is informational only; the pin wins regardless
+    let score = match_score(&clean_song_title(&title), &artist, &result.title, &result.primary_artist.name);
+    let metadata = EntryMetadata::new("genius")
+        .with_source(result.url.clone(), Some(score))
+        .with_scraper_version(SCRAPER_VERSION)
+        .with_model(formatted_by);
+    
+    // A lookup still running for this track would overwrite the pinned lyrics when it finished
+    crate::inflight::cancel(&app, &inflight_key(&cache_key));
+    
+    let cache = app.state::<ContentCache>();
+    cache.remove(CacheCategory::LyricsMiss, &cache_key);
+    cache.insert(CacheCategory::Lyrics, cache_key.clone(), lyrics.clone(), metadata);
+    cache.insert(CacheCategory::LyricsPin, cache_key, result.url, EntryMetadata::new("user"));
+    cache.flush();
+    
+    Ok(lyrics)
+}
+
+// Remove a pinned lyrics source. The cached lyrics are dropped too, so the next
+// lookup goes back to automatic matching. Returns false if nothing was pinned.
+#[tauri::command]
+pub fn unpin_lyrics_source(title: String, artist: String, app: tauri::AppHandle) -> Result<bool, String> {
+    use crate::cache::{CacheCategory, ContentCache};
+    use crate::utils::normalize_string;
+    use tauri::Manager;
+
+    let cache_key = format!("{}|{}", 
+        normalize_string(&artist), 
+        normalize_string(&title)
+    );
+    
+    let cache = app.state::<ContentCache>();
+    let was_pinned = cache.remove(CacheCategory::LyricsPin, &cache_key);
+    if was_pinned {
+        cache.remove(CacheCategory::Lyrics, &cache_key);
+        cache.flush();
+    }
+    
+    Ok(was_pinned)
+}
+
+// Forget failed lyrics lookups so they are retried: for one track if title and
+// artist are given, otherwise all of them. Returns how many were cleared.
+#[tauri::command]
+pub fn clear_lyrics_misses(title: Option<String>, artist: Option<String>, app: tauri::AppHandle) -> Result<usize, String> {
+    use crate::cache::{CacheCategory, ContentCache};
+    use crate::utils::normalize_string;
+    use tauri::Manager;
+
+    let cache = app.state::<ContentCache>();
+    let cleared = match (title, artist) {
+        (Some(title), Some(artist)) => {
+            let cache_key = format!("{}|{}", normalize_string(&artist), normalize_string(&title));
+            usize::from(cache.remove(CacheCategory::LyricsMiss, &cache_key))
+        }
+        (None, None) => cache.purge(CacheCategory::LyricsMiss),
+        _ => return Err("Provide both title and artist, or neither to clear all".to_string()),
+    };
+    
+    cache.flush();
+    Ok(cleared)
 }
 
 #[tauri::command]
@@ -184,29 +400,11 @@ pub async fn search_lyrics(title: String, artist: String, app: tauri::AppHandle)
         urlencoding::encode(&search_query)
     );
     
-    let client = reqwest::Client::builder()
-        .user_agent("Basitune/0.1.0 (https://github.com/basiphobe/Basitune)")
-        .timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = crate::http::client(&app)?;
     
     let genius_token = get_genius_token(&app)
-        .ok_or_else(|| "Genius API token not configured. Please add it to config.json in your app data directory.".to_string())?;
-    let response = client
-        .get(&search_url)
-        .header("Authorization", format!("Bearer {}", genius_token))
-        .send()
-        .await
-        .map_err(|e| format!("Search request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Genius API returned status: {}", response.status()));
-    }
-    
-    let search_result: GeniusSearchResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse search results: {}", e))?;
+        .ok_or_else(|| "Genius API token not configured. Please add it in Settings.".to_string())?;
+    let search_result = search_genius(&client, &search_url, &genius_token).await?;
     
     // Return top 10 results for suggestions (don't filter here - let user see all matches)
     // The filtering happens in get_lyrics to prevent auto-fetching non-song content
@@ -0,0 +1,119 @@
+// Minimal HTTP server for tests: answers each connection with the next canned response
+// and records the requests it received.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub request_line: String,
+    // Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct MockServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    // Serve `responses` in order, one per connection. The server stops after the last one.
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = match listener.accept() {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                if let Some(request) = read_request(&mut reader) {
+                    recorded.lock().unwrap().push(request);
+                }
+
+                let reason = if response.status < 400 { "OK" } else { "Error" };
+                let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+                for (name, value) in &response.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(response.body.as_bytes());
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Option<RecordedRequest> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(RecordedRequest {
+        request_line: request_line.trim_end().to_string(),
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
@@ -1,8 +1,20 @@
 pub mod openai;
 pub mod genius;
+pub mod chat;
+pub mod profile;
+pub mod prompts;
+pub mod provider;
+pub mod sse;
+pub mod streaming;
+pub mod usage;
+
+// Also used by the tests in crate::http
+#[cfg(test)]
+pub(crate) mod mock_server;
 
 // Re-export commands for tauri's generate_handler!
 pub use openai::get_artist_info;
 pub use openai::get_song_context;
+pub use profile::get_artist_profile;
 pub use genius::get_lyrics;
 pub use genius::search_lyrics;
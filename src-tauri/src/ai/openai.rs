@@ -1,11 +1,34 @@
+use super::provider::{AiFeature, Completion, CompletionRequest, LlmProvider, OnDelta, TokenUsage};
+use super::sse::SseDecoder;
+use crate::http::{send_with_retry, HttpError, RetryPolicy};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::BTreeMap;
+
+// OpenAI's chat completions API, and any server that implements it
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_MODEL: &str = "gpt-4o-mini";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenAIRequest {
     pub model: String,
     pub messages: Vec<OpenAIMessage>,
     pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAIStreamOptions>,
+    // {"type": "json_schema", "json_schema": {...}} for structured replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenAIStreamOptions {
+    // Adds a final event with the token usage
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,129 +40,575 @@ pub struct OpenAIMessage {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenAIResponse {
     pub choices: Vec<OpenAIChoice>,
+    #[serde(default)]
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenAIUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl From<OpenAIUsage> for TokenUsage {
+    fn from(usage: OpenAIUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenAIChoice {
     pub message: OpenAIMessage,
+    // "length" when the reply hit max_tokens
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
-pub async fn call_openai(prompt: String, max_tokens: u32, app_handle: &tauri::AppHandle) -> Result<String, String> {
-    let api_key = crate::config::get_openai_key(app_handle)
-        .ok_or_else(|| "OpenAI API key not configured. Please add it to config.json in your app data directory.".to_string())?;
-    
-    let request = OpenAIRequest {
-        model: "gpt-4o-mini".to_string(),
-        messages: vec![OpenAIMessage {
-            role: "user".to_string(),
-            content: prompt,
-        }],
-        max_tokens,
-    };
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .map_err(|e| e.to_string())?;
-    
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("OpenAI request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("OpenAI API error {}: {}", status, error_text));
+// One server-sent event of a streamed completion
+#[derive(Debug, Deserialize)]
+pub struct OpenAIStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<OpenAIStreamChoice>,
+    #[serde(default)]
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAIStreamChoice {
+    #[serde(default)]
+    pub delta: OpenAIDelta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenAIDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+pub struct OpenAiCompatible {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    headers: BTreeMap<String, String>,
+    client: reqwest::Client,
+}
+
+impl OpenAiCompatible {
+    // `base_url` is the API root, e.g. "https://api.openai.com/v1" or
+    // "http://localhost:11434/v1". OpenAI itself needs an API key; self-hosted servers
+    // often don't, so the key is optional for them. Timeouts are set per request from the
+    // feature's settings, overriding the client's default.
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        api_key: Option<String>,
+        headers: BTreeMap<String, String>,
+        client: reqwest::Client,
+    ) -> Result<Self, String> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        if base_url == DEFAULT_BASE_URL && api_key.is_none() {
+            return Err("OpenAI API key not configured. Please add it in Settings.".to_string());
+        }
+
+        Ok(Self {
+            base_url,
+            model: model.to_string(),
+            api_key,
+            headers,
+            client,
+        })
+    }
+
+    fn is_openai(&self) -> bool {
+        self.base_url == DEFAULT_BASE_URL
+    }
+
+    // Body for `request`, and the model it asks for
+    fn request_body(&self, request: CompletionRequest, stream: bool) -> (OpenAIRequest, String) {
+        let model = request.model.unwrap_or_else(|| self.model.clone());
+        let body = OpenAIRequest {
+            model: model.clone(),
+            messages: request
+                .history
+                .into_iter()
+                .map(|message| OpenAIMessage { role: message.role.as_str().to_string(), content: message.content })
+                .chain(std::iter::once(OpenAIMessage { role: "user".to_string(), content: request.prompt }))
+                .collect(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            stream,
+            // Not every compatible server accepts stream_options; usage is estimated for those
+            stream_options: (stream && self.is_openai()).then_some(OpenAIStreamOptions { include_usage: true }),
+            response_format: request.json_schema.map(|schema| {
+                serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": schema.name, "strict": true, "schema": schema.schema },
+                })
+            }),
+        };
+        (body, model)
+    }
+
+    async fn send(&self, body: &OpenAIRequest, timeout: std::time::Duration) -> Result<reqwest::Response, String> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let build = || {
+            let mut builder = self
+                .client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .timeout(timeout)
+                .json(body);
+            if let Some(api_key) = &self.api_key {
+                builder = builder.header("Authorization", format!("Bearer {}", api_key));
+            }
+            for (name, value) in &self.headers {
+                builder = builder.header(name, value);
+            }
+            builder
+        };
+
+        send_with_retry(&RetryPolicy::default(), "AI request", build)
+            .await
+            .map_err(|e| match e {
+                e if e.is_unauthorized() => format!("AI provider rejected the API key ({}). Check it in Settings.", e),
+                e if e.is_quota_exhausted() => format!("AI provider quota is used up ({}). Check your plan and billing.", e),
+                HttpError::Status { .. } => format!("AI provider error {}", e),
+                HttpError::Network { .. } => format!("AI request to {} failed: {}", self.base_url, e),
+            })
+    }
+}
+
+// Text of every message sent, for estimating prompt tokens
+fn prompt_of(body: &OpenAIRequest) -> String {
+    body.messages.iter().map(|message| message.content.as_str()).collect::<Vec<_>>().join("\n")
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &str {
+        if self.is_openai() { "openai" } else { "openai-compatible" }
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<Completion, String> {
+        let timeout = request.timeout;
+        let (body, model) = self.request_body(request, false);
+        let response = self.send(&body, timeout).await?;
+
+        let result: OpenAIResponse = response.json().await
+            .map_err(|e| format!("Failed to parse AI response: {}", e))?;
+
+        let usage = result.usage;
+        let choice = result
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No response from the AI provider".to_string())?;
+        Ok(Completion {
+            usage: usage.map(TokenUsage::from).unwrap_or_else(|| TokenUsage::estimate(&prompt_of(&body), &choice.message.content)),
+            text: choice.message.content,
+            model,
+            truncated: choice.finish_reason.as_deref() == Some("length"),
+        })
+    }
+
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        on_delta: &OnDelta<'_>,
+    ) -> Result<Completion, String> {
+        let timeout = request.timeout;
+        let (body, model) = self.request_body(request, true);
+        let mut response = self.send(&body, timeout).await?;
+
+        let mut decoder = SseDecoder::default();
+        let mut completion = Completion { text: String::new(), model, truncated: false, usage: TokenUsage::default() };
+        let mut reported_usage = None;
+        let mut finished = false;
+
+        while !finished {
+            let events = match response.chunk().await.map_err(|e| format!("AI stream interrupted: {}", e))? {
+                Some(bytes) => decoder.push(&bytes),
+                None => {
+                    finished = true;
+                    decoder.finish().into_iter().collect()
+                }
+            };
+
+            for data in events {
+                // OpenAI ends the stream with a [DONE] event; other servers just close it
+                if data == "[DONE]" {
+                    finished = true;
+                    break;
+                }
+                let chunk: OpenAIStreamChunk = serde_json::from_str(&data)
+                    .map_err(|e| format!("Failed to parse AI stream: {}", e))?;
+                if let Some(usage) = chunk.usage {
+                    reported_usage = Some(TokenUsage::from(usage));
+                }
+                for choice in chunk.choices {
+                    if let Some(delta) = choice.delta.content.filter(|delta| !delta.is_empty()) {
+                        on_delta(&delta);
+                        completion.text.push_str(&delta);
+                    }
+                    if choice.finish_reason.as_deref() == Some("length") {
+                        completion.truncated = true;
+                    }
+                }
+            }
+        }
+
+        if completion.text.is_empty() {
+            return Err("No response from the AI provider".to_string());
+        }
+        completion.usage = reported_usage.unwrap_or_else(|| TokenUsage::estimate(&prompt_of(&body), &completion.text));
+        Ok(completion)
     }
-    
-    let result: OpenAIResponse = response.json().await
-        .map_err(|e| format!("Failed to parse OpenAI response: {}", e))?;
-    
-    result
-        .choices
-        .first()
-        .map(|choice| choice.message.content.clone())
-        .ok_or_else(|| "No response from OpenAI".to_string())
 }
 
+// With a request_id the text is also streamed to the sidebar as ai-chunk events; only the
+// finished text is cached
 #[tauri::command]
-pub async fn get_artist_info(artist: String, app: tauri::AppHandle) -> Result<String, String> {
-    use crate::cache::{load_cache, update_artist_info};
+pub async fn get_artist_info(artist: String, request_id: Option<String>, app: tauri::AppHandle) -> Result<String, String> {
+    use crate::cache::{CacheCategory, ContentCache, EntryMetadata};
     use crate::utils::normalize_string;
+    use tauri::Manager;
 
-    // Create cache key (normalized artist name)
-    let cache_key = normalize_string(&artist);
+    // Cache key is the normalized artist name, plus the prompt template and language if customized
+    let settings = crate::config::load_config(&app).ai;
+    let prompt = crate::ai::prompts::artist_info(&settings, &artist, normalize_string(&artist));
     
     // Try to load from cache
-    let cache = load_cache(&app);
+    let cache = app.state::<ContentCache>();
     
-    if let Some(cached_info) = cache.artist_info.get(&cache_key) {
+    if let Some(cached_info) = cache.get(CacheCategory::ArtistInfo, &prompt.cache_key) {
         // Reject empty cached values (from previous API failures)
         if !cached_info.trim().is_empty() {
-            return Ok(cached_info.clone());
+            return Ok(cached_info);
         }
     }
     
-    let prompt = format!(
-        "Provide a brief, 2-3 paragraph summary about the music artist/band '{}'. Include their genre, notable achievements, and impact on music. Keep it concise and informative.",
-        artist
-    );
-    
-    let result = call_openai(prompt, 500, &app).await?;
-    
-    // Save to cache atomically
-    update_artist_info(&app, cache_key, result.clone());
-    
-    Ok(result)
+    // Identical lookups already running share this one's result, streamed to each caller
+    let key = format!("{}:{}", CacheCategory::ArtistInfo.as_str(), prompt.cache_key);
+    crate::inflight::coalesce_streamed(&app, &key, &normalize_string(&artist), request_id.as_deref(), async {
+        crate::ai::usage::check_budget(&app, &settings)?;
+        let provider = crate::ai::provider::for_app(&app, &settings)?;
+        let request = AiFeature::ArtistInfo.request(&settings, prompt.text);
+        let completion = crate::ai::streaming::complete(&app, request_id.as_deref(), provider.as_ref(), request).await?;
+        crate::ai::usage::record(&app, AiFeature::ArtistInfo, provider.name(), &settings, &completion);
+        
+        // Save to cache (flushed to disk in the background)
+        let metadata = EntryMetadata::new(provider.name()).with_model(Some(completion.model));
+        cache.insert(CacheCategory::ArtistInfo, prompt.cache_key, completion.text.clone(), metadata);
+        
+        Ok(completion.text)
+    })
+    .await
 }
 
+// `album` is filled into {album} in the prompt template; it doesn't affect the cache key
 #[tauri::command]
-pub async fn get_song_context(title: String, artist: String, app: tauri::AppHandle) -> Result<String, String> {
-    use crate::cache::{load_cache, update_song_context};
+pub async fn get_song_context(title: String, artist: String, album: Option<String>, request_id: Option<String>, app: tauri::AppHandle) -> Result<String, String> {
+    use crate::cache::{CacheCategory, ContentCache, EntryMetadata};
     use crate::utils::normalize_string;
+    use tauri::Manager;
 
-    // Create cache key (normalized artist + title)
-    let cache_key = format!("{}|{}", 
+    // Cache key is normalized artist + title, plus the prompt template and language if customized
+    let settings = crate::config::load_config(&app).ai;
+    let base_key = format!("{}|{}", 
         normalize_string(&artist), 
         normalize_string(&title)
     );
+    let prompt = crate::ai::prompts::song_context(&settings, &artist, &title, album.as_deref(), base_key.clone());
     
     // Try to load from cache
-    let cache = load_cache(&app);
+    let cache = app.state::<ContentCache>();
     
-    if let Some(cached_context) = cache.song_context.get(&cache_key) {
+    if let Some(cached_context) = cache.get(CacheCategory::SongContext, &prompt.cache_key) {
         // Reject empty cached values (from previous API failures)
         if !cached_context.trim().is_empty() {
-            return Ok(cached_context.clone());
+            return Ok(cached_context);
         }
     }
     
-    let prompt = format!(
-        "Provide a brief analysis of the song '{}' by {}. Focus on its themes, meaning, and musical significance. Keep it to 2-3 paragraphs.",
-        title, artist
-    );
-    
-    let result = call_openai(prompt, 500, &app).await?;
-    
-    // Save to cache atomically
-    update_song_context(&app, cache_key, result.clone());
-    
-    Ok(result)
+    // Identical lookups already running share this one's result, streamed to each caller
+    let key = format!("{}:{}", CacheCategory::SongContext.as_str(), prompt.cache_key);
+    crate::inflight::coalesce_streamed(&app, &key, &base_key, request_id.as_deref(), async {
+        crate::ai::usage::check_budget(&app, &settings)?;
+        let provider = crate::ai::provider::for_app(&app, &settings)?;
+        let request = AiFeature::SongContext.request(&settings, prompt.text);
+        let completion = crate::ai::streaming::complete(&app, request_id.as_deref(), provider.as_ref(), request).await?;
+        crate::ai::usage::record(&app, AiFeature::SongContext, provider.name(), &settings, &completion);
+        
+        // Save to cache (flushed to disk in the background)
+        let metadata = EntryMetadata::new(provider.name()).with_model(Some(completion.model));
+        cache.insert(CacheCategory::SongContext, prompt.cache_key, completion.text.clone(), metadata);
+        
+        Ok(completion.text)
+    })
+    .await
 }
 
-pub async fn format_lyrics_with_ai(raw_lyrics: &str, app_handle: &tauri::AppHandle) -> Result<String, String> {
-    let prompt = format!(
-        "Clean and format this text. Remove any web page elements like headers, footers, \
-        contributor names, 'Embed' text, navigation elements, advertisements, or metadata. \
-        Keep only the main content with proper structure and formatting. Preserve line breaks \
-        and spacing that are part of the content structure. Return ONLY the cleaned text.\n\n{}",
-        raw_lyrics
-    );
-    
-    call_openai(prompt, 500, app_handle).await
+pub async fn format_lyrics_with_ai(raw_lyrics: &str, app_handle: &tauri::AppHandle) -> Result<Completion, String> {
+    let settings = crate::config::load_config(app_handle).ai;
+    crate::ai::usage::check_budget(app_handle, &settings)?;
+    let provider = crate::ai::provider::for_app(app_handle, &settings)?;
+    let record = |completion: &Completion| {
+        crate::ai::usage::record(app_handle, AiFeature::LyricsFormatting, provider.name(), &settings, completion)
+    };
+    format_lyrics(provider.as_ref(), &settings, raw_lyrics, &record).await
+}
+
+// Format lyrics in chunks small enough that each reply fits in max_tokens, so a long song
+// comes back complete. A reply that is cut off anyway fails the whole call, letting the
+// caller fall back to regex cleanup instead of showing half a song.
+// `record` is called with each chunk's completion, so tokens spent on a failed call count too.
+async fn format_lyrics(
+    provider: &dyn LlmProvider,
+    settings: &crate::config::AiSettings,
+    raw_lyrics: &str,
+    record: &(dyn Fn(&Completion) + Sync),
+) -> Result<Completion, String> {
+    // The reply is about as long as the input; ~3 bytes per token leaves headroom
+    let chunk_size = AiFeature::LyricsFormatting.max_tokens(settings) as usize * 3;
+
+    let mut parts = Vec::new();
+    let mut model = provider.model().to_string();
+    let mut usage = TokenUsage::default();
+    for chunk in split_into_chunks(raw_lyrics, chunk_size) {
+        let prompt = format!(
+            "Clean and format this text. Remove any web page elements like headers, footers, \
+            contributor names, 'Embed' text, navigation elements, advertisements, or metadata. \
+            Keep only the main content with proper structure and formatting. Preserve line breaks \
+            and spacing that are part of the content structure. Return ONLY the cleaned text.\n\n{}",
+            chunk
+        );
+
+        let completion = provider.complete(AiFeature::LyricsFormatting.request(settings, prompt)).await?;
+        record(&completion);
+        usage += completion.usage;
+        if completion.truncated {
+            return Err("AI response was cut off before the end of the lyrics".to_string());
+        }
+        parts.push(completion.text.trim().to_string());
+        model = completion.model;
+    }
+
+    Ok(Completion {
+        text: parts.join("\n\n"),
+        model,
+        truncated: false,
+        usage,
+    })
+}
+
+// Split text into chunks of at most `max_len` bytes, preferring to break between stanzas,
+// then between lines, and only cutting a line when it is longer than a chunk on its own.
+fn split_into_chunks(text: &str, max_len: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    for stanza in text.split_inclusive("\n\n") {
+        if stanza.len() <= max_len {
+            pieces.push(stanza);
+            continue;
+        }
+        for line in stanza.split_inclusive('\n') {
+            let mut rest = line;
+            while rest.len() > max_len {
+                let mut at = max_len;
+                while !rest.is_char_boundary(at) {
+                    at -= 1;
+                }
+                pieces.push(&rest[..at]);
+                rest = &rest[at..];
+            }
+            pieces.push(rest);
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        if !current.is_empty() && current.len() + piece.len() > max_len {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(piece);
+    }
+    chunks.push(current);
+    chunks.retain(|chunk| !chunk.trim().is_empty());
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock_server::{MockResponse, MockServer};
+    use crate::ai::provider::JsonSchema;
+
+    const REPLY: &str = r#"{"choices":[{"message":{"role":"assistant","content":"Hello from the LAN"}}],"usage":{"prompt_tokens":9,"completion_tokens":5,"total_tokens":14}}"#;
+
+    #[tokio::test]
+    async fn sends_requests_to_configured_endpoint() {
+        let server = MockServer::start(vec![MockResponse::json(200, REPLY)]);
+        let headers = BTreeMap::from([("X-Team".to_string(), "music".to_string())]);
+        let provider = OpenAiCompatible::new(&format!("{}/v1/", server.base_url), "llama3", Some("local-key".to_string()), headers, reqwest::Client::new()).unwrap();
+
+        let completion = provider.complete(CompletionRequest::new("Hi".to_string(), 50)).await.unwrap();
+        let usage = TokenUsage { prompt_tokens: 9, completion_tokens: 5 };
+        assert_eq!(completion, Completion { text: "Hello from the LAN".to_string(), model: "llama3".to_string(), truncated: false, usage });
+        assert_eq!(provider.name(), "openai-compatible");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.request_line, "POST /v1/chat/completions HTTP/1.1");
+        assert_eq!(request.header("authorization"), Some("Bearer local-key"));
+        assert_eq!(request.header("x-team"), Some("music"));
+
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["model"], "llama3");
+        assert_eq!(body["max_tokens"], 50);
+        assert_eq!(body["messages"][0]["content"], "Hi");
+    }
+
+    #[tokio::test]
+    async fn self_hosted_servers_do_not_need_a_key() {
+        let server = MockServer::start(vec![MockResponse::json(200, REPLY)]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        provider.complete(CompletionRequest::new("Hi".to_string(), 50)).await.unwrap();
+        assert_eq!(server.requests()[0].header("authorization"), None);
+
+        // OpenAI itself does
+        assert!(OpenAiCompatible::new(DEFAULT_BASE_URL, DEFAULT_MODEL, None, BTreeMap::new(), reqwest::Client::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn error_responses_are_reported() {
+        let server = MockServer::start(vec![MockResponse::json(404, r#"{"error":"model not found"}"#)]);
+        let provider = OpenAiCompatible::new(&server.base_url, "missing", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        let err = provider.complete(CompletionRequest::new("Hi".to_string(), 50)).await.unwrap_err();
+        assert!(err.contains("404") && err.contains("model not found"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn request_overrides_model_temperature_and_timeout() {
+        let server = MockServer::start(vec![MockResponse::json(200, REPLY)]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        let mut request = CompletionRequest::new("Hi".to_string(), 50);
+        request.model = Some("mistral".to_string());
+        request.temperature = Some(0.2);
+        let completion = provider.complete(request).await.unwrap();
+        assert_eq!(completion.model, "mistral");
+
+        let body: serde_json::Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["model"], "mistral");
+        assert_eq!(body["temperature"], 0.2);
+
+        // Temperature is left out unless set
+        let request = OpenAIRequest { model: "m".to_string(), messages: Vec::new(), max_tokens: 1, temperature: None, stream: false, stream_options: None, response_format: None };
+        let body = serde_json::to_value(request).unwrap();
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("stream").is_none());
+        assert!(body.get("response_format").is_none());
+    }
+
+    #[tokio::test]
+    async fn json_schema_is_sent_as_response_format() {
+        let server = MockServer::start(vec![MockResponse::json(200, REPLY)]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        let mut request = CompletionRequest::new("Hi".to_string(), 50);
+        let schema = serde_json::json!({ "type": "object", "properties": {}, "additionalProperties": false });
+        request.json_schema = Some(JsonSchema { name: "greeting".to_string(), schema: schema.clone() });
+        provider.complete(request).await.unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["name"], "greeting");
+        assert_eq!(body["response_format"]["json_schema"]["strict"], true);
+        assert_eq!(body["response_format"]["json_schema"]["schema"], schema);
+    }
+
+    #[test]
+    fn lyrics_are_split_at_stanzas_then_lines() {
+        let text = "aaaa\nbbbb\n\ncccc\ndddd\n\neeee";
+        assert_eq!(split_into_chunks(text, 100), vec![text]);
+        assert_eq!(split_into_chunks(text, 12), vec!["aaaa\nbbbb\n\n", "cccc\ndddd\n\n", "eeee"]);
+        assert_eq!(split_into_chunks(text, 6), vec!["aaaa\n", "bbbb\n\n", "cccc\n", "dddd\n\n", "eeee"]);
+
+        // Over-long lines are cut on character boundaries
+        let chunks = split_into_chunks("ééééé", 4);
+        assert_eq!(chunks, vec!["éé", "éé", "é"]);
+        assert!(split_into_chunks("  \n\n", 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn long_lyrics_are_formatted_in_chunks() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, r#"{"choices":[{"message":{"role":"assistant","content":"Verse one\n"},"finish_reason":"stop"}]}"#),
+            MockResponse::json(200, r#"{"choices":[{"message":{"role":"assistant","content":"Verse two"},"finish_reason":"stop"}]}"#),
+        ]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+        let mut settings = crate::config::AiSettings::default();
+        settings.lyrics_formatting.max_tokens = Some(16);
+
+        let stanza = "la ".repeat(12);
+        let raw = format!("{}\n\n{}", stanza, stanza);
+        let recorded = std::sync::Mutex::new(0);
+        let record = |_: &Completion| *recorded.lock().unwrap() += 1;
+        let completion = format_lyrics(&provider, &settings, &raw, &record).await.unwrap();
+        assert_eq!(completion.text, "Verse one\n\nVerse two");
+        assert_eq!(*recorded.lock().unwrap(), 2);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["max_tokens"], 16);
+        assert_eq!(body["temperature"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn truncated_lyrics_are_an_error() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"choices":[{"message":{"role":"assistant","content":"Verse one"},"finish_reason":"length"}]}"#,
+        )]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        let err = format_lyrics(&provider, &crate::config::AiSettings::default(), "Verse one\nVerse two", &|_| {}).await.unwrap_err();
+        assert!(err.contains("cut off"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn streamed_replies_are_passed_on_as_they_arrive() {
+        let events = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello \"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"world\"},\"finish_reason\":\"length\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let server = MockServer::start(vec![MockResponse::json(200, events).with_header("Content-Type", "text/event-stream")]);
+        let provider = OpenAiCompatible::new(&server.base_url, "llama3", None, BTreeMap::new(), reqwest::Client::new()).unwrap();
+
+        let deltas = std::sync::Mutex::new(Vec::new());
+        let on_delta = |delta: &str| deltas.lock().unwrap().push(delta.to_string());
+        let completion = provider.complete_streaming(CompletionRequest::new("Hi".to_string(), 50), &on_delta).await.unwrap();
+
+        assert_eq!(deltas.into_inner().unwrap(), vec!["Hello ", "world"]);
+        // The mock server doesn't report usage, so it is estimated
+        let usage = TokenUsage { prompt_tokens: 1, completion_tokens: 3 };
+        assert_eq!(completion, Completion { text: "Hello world".to_string(), model: "llama3".to_string(), truncated: true, usage });
+
+        let body: serde_json::Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["stream"], true);
+    }
 }
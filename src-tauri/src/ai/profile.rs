@@ -0,0 +1,299 @@
+// Structured artist profiles. Instead of a free-text bio, the provider is asked for a JSON
+// object matching `schema()`, which gives the sidebar real fields (genres, origin, members,
+// similar artists) to build links and chips from. Replies are parsed strictly; when the
+// provider can't produce a valid one, the profile falls back to the prose bio from
+// get_artist_info.
+
+use super::provider::{AiFeature, CompletionRequest, JsonSchema};
+use super::streaming::CANCELLED;
+use crate::config::AiSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+// Lists are cut to this many items; the sidebar shows them as a row of chips
+const MAX_LIST_ITEMS: usize = 12;
+
+// The same facts take more tokens as JSON than as prose
+const MIN_MAX_TOKENS: u32 = 800;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtistProfile {
+    pub name: String,
+    // Bio written from the artist info prompt template
+    pub summary: String,
+    pub genres: Vec<String>,
+    // Where the artist is from, e.g. "Seattle, Washington, US"
+    pub origin: Option<String>,
+    // e.g. "1987–1994" or "2009–present"
+    pub years_active: Option<String>,
+    // Band members; empty for solo artists
+    pub members: Vec<String>,
+    pub similar_artists: Vec<String>,
+    // False for a profile built from the prose bio, which has nothing but the summary
+    #[serde(default)]
+    pub structured: bool,
+}
+
+// The reply as the schema describes it. Unknown fields and wrong types are errors rather
+// than being dropped, so a server that ignored the schema isn't mistaken for one that followed it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileReply {
+    name: String,
+    summary: String,
+    genres: Vec<String>,
+    origin: Option<String>,
+    years_active: Option<String>,
+    members: Vec<String>,
+    similar_artists: Vec<String>,
+}
+
+impl ArtistProfile {
+    // Parse a structured reply, trimming fields and dropping blank or repeated list items
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let reply: ProfileReply = serde_json::from_str(strip_code_fence(text))
+            .map_err(|e| format!("Invalid artist profile: {}", e))?;
+
+        let name = reply.name.trim().to_string();
+        let summary = reply.summary.trim().to_string();
+        if name.is_empty() || summary.is_empty() {
+            return Err("Invalid artist profile: name and summary are required".to_string());
+        }
+
+        Ok(Self {
+            name,
+            summary,
+            genres: clean_list(reply.genres),
+            origin: clean_option(reply.origin),
+            years_active: clean_option(reply.years_active),
+            members: clean_list(reply.members),
+            similar_artists: clean_list(reply.similar_artists),
+            structured: true,
+        })
+    }
+
+    // Wrap a prose bio so callers always get the same shape
+    pub fn from_prose(artist: &str, bio: String) -> Self {
+        Self {
+            name: artist.trim().to_string(),
+            summary: bio.trim().to_string(),
+            genres: Vec::new(),
+            origin: None,
+            years_active: None,
+            members: Vec::new(),
+            similar_artists: Vec::new(),
+            structured: false,
+        }
+    }
+}
+
+// Schema for the reply. Strict mode requires every property to be listed as required, so
+// fields that may be unknown are nullable instead of optional.
+pub fn schema() -> JsonSchema {
+    let text = json!({ "type": "string" });
+    let nullable = json!({ "type": ["string", "null"] });
+    let list = json!({ "type": "array", "items": { "type": "string" } });
+
+    JsonSchema {
+        name: "artist_profile".to_string(),
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "name": text,
+                "summary": text,
+                "genres": list,
+                "origin": nullable,
+                "years_active": nullable,
+                "members": list,
+                "similar_artists": list,
+            },
+            "required": ["name", "summary", "genres", "origin", "years_active", "members", "similar_artists"],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+// Request for a profile. `summary_prompt` is the artist info prompt, so the summary follows
+// the user's template and language.
+pub fn request(ai: &AiSettings, summary_prompt: &str) -> CompletionRequest {
+    let prompt = format!(
+        "{}\n\nReply with a JSON object only. Put that text in \"summary\" and the artist's name in \"name\", \
+        and fill in \"genres\", \"origin\", \"years_active\", \"members\" (for a band) and \"similar_artists\". \
+        Use null or an empty list for anything you aren't sure of.",
+        summary_prompt
+    );
+
+    let mut request = AiFeature::ArtistInfo.request(ai, prompt);
+    request.max_tokens = request.max_tokens.max(MIN_MAX_TOKENS);
+    request.json_schema = Some(schema());
+    request
+}
+
+// Servers without structured output sometimes wrap the JSON in a Markdown code block
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    match text.strip_prefix("```").and_then(|rest| rest.strip_suffix("```")) {
+        Some(inner) => inner.trim_start_matches("json").trim(),
+        None => text,
+    }
+}
+
+fn clean_option(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn clean_list(items: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim();
+        if !item.is_empty() && !cleaned.iter().any(|seen| seen.eq_ignore_ascii_case(item)) {
+            cleaned.push(item.to_string());
+        }
+    }
+    cleaned.truncate(MAX_LIST_ITEMS);
+    cleaned
+}
+
+// Structured profile for `artist`, cached under the same key as the prose bio. With a
+// request_id the request can be cancelled like a streaming one. If the provider can't
+// produce a valid profile, the prose bio is returned instead (streamed, when a request_id
+// was given); that fallback isn't cached as a profile, so a later call tries again.
+#[tauri::command]
+pub async fn get_artist_profile(artist: String, request_id: Option<String>, app: tauri::AppHandle) -> Result<ArtistProfile, String> {
+    use crate::cache::{CacheCategory, ContentCache};
+    use crate::utils::normalize_string;
+    use tauri::Manager;
+
+    let settings = crate::config::load_config(&app).ai;
+    let prompt = super::prompts::artist_info(&settings, &artist, normalize_string(&artist));
+
+    let cache = app.state::<ContentCache>();
+    if let Some(cached) = cache.get(CacheCategory::ArtistProfile, &prompt.cache_key) {
+        match serde_json::from_str(&cached) {
+            Ok(profile) => return Ok(profile),
+            Err(e) => log_warn!("[Basitune] Ignoring unreadable cached artist profile: {}", e),
+        }
+    }
+
+    // Identical lookups already running share this one's result, the profile JSON as cached
+    let key = format!("{}:{}", CacheCategory::ArtistProfile.as_str(), prompt.cache_key);
+    let fetched = crate::inflight::coalesce(&app, &key, &normalize_string(&artist), async {
+        let (profile, metadata) = fetch_profile(&app, request_id.as_deref(), &settings, &prompt.text).await?;
+        let json = serde_json::to_string(&profile).map_err(|e| format!("Failed to serialize artist profile: {}", e))?;
+        cache.insert(CacheCategory::ArtistProfile, prompt.cache_key.clone(), json.clone(), metadata);
+        Ok(json)
+    })
+    .await
+    .and_then(|json| serde_json::from_str::<ArtistProfile>(&json).map_err(|e| e.to_string()));
+
+    match fetched {
+        Ok(profile) => Ok(profile),
+        Err(e) if e == CANCELLED => Err(e),
+        Err(e) => {
+            log_warn!("[Basitune] No structured profile for {} ({}); using the prose bio", artist, e);
+            let bio = super::openai::get_artist_info(artist.clone(), request_id, app).await?;
+            Ok(ArtistProfile::from_prose(&artist, bio))
+        }
+    }
+}
+
+async fn fetch_profile(
+    app_handle: &tauri::AppHandle,
+    request_id: Option<&str>,
+    settings: &AiSettings,
+    summary_prompt: &str,
+) -> Result<(ArtistProfile, crate::cache::EntryMetadata), String> {
+    super::usage::check_budget(app_handle, settings)?;
+    let provider = super::provider::for_app(app_handle, settings)?;
+    let request = request(settings, summary_prompt);
+    let completion = match request_id {
+        Some(request_id) => super::streaming::cancellable(app_handle, request_id, provider.complete(request)).await?,
+        None => provider.complete(request).await?,
+    };
+    super::usage::record(app_handle, AiFeature::ArtistInfo, provider.name(), settings, &completion);
+
+    if completion.truncated {
+        return Err("artist profile was cut off".to_string());
+    }
+    let profile = ArtistProfile::parse(&completion.text)?;
+    let metadata = crate::cache::EntryMetadata::new(provider.name()).with_model(Some(completion.model));
+    Ok((profile, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_are_parsed_and_cleaned() {
+        let reply = r#"```json
+        {
+            "name": " Nirvana ",
+            "summary": "Grunge band from Aberdeen.",
+            "genres": ["Grunge", "", "grunge", "Alternative rock"],
+            "origin": " Aberdeen, Washington, US ",
+            "years_active": " ",
+            "members": ["Kurt Cobain", "Krist Novoselic", "Dave Grohl"],
+            "similar_artists": ["Mudhoney"]
+        }
+        ```"#;
+
+        let profile = ArtistProfile::parse(reply).unwrap();
+        assert_eq!(profile.name, "Nirvana");
+        assert_eq!(profile.genres, vec!["Grunge", "Alternative rock"]);
+        assert_eq!(profile.origin.as_deref(), Some("Aberdeen, Washington, US"));
+        assert_eq!(profile.years_active, None);
+        assert_eq!(profile.members.len(), 3);
+        assert!(profile.structured);
+
+        // Round-trips through the cache
+        let cached = serde_json::to_string(&profile).unwrap();
+        assert_eq!(serde_json::from_str::<ArtistProfile>(&cached).unwrap(), profile);
+    }
+
+    #[test]
+    fn malformed_replies_are_rejected() {
+        let valid = json!({
+            "name": "Nirvana", "summary": "Grunge band.", "genres": [], "origin": null,
+            "years_active": null, "members": [], "similar_artists": [],
+        });
+        assert!(ArtistProfile::parse(&valid.to_string()).is_ok());
+
+        let mut unknown = valid.clone();
+        unknown["label"] = json!("Sub Pop");
+        let mut missing = valid.clone();
+        missing.as_object_mut().unwrap().remove("genres");
+        let mut wrong_type = valid.clone();
+        wrong_type["members"] = json!("Kurt Cobain");
+        let mut blank = valid.clone();
+        blank["summary"] = json!("  ");
+
+        for reply in [unknown, missing, wrong_type, blank] {
+            assert!(ArtistProfile::parse(&reply.to_string()).is_err(), "{}", reply);
+        }
+        assert!(ArtistProfile::parse("Nirvana were a grunge band.").is_err());
+    }
+
+    #[test]
+    fn request_asks_for_the_schema() {
+        let mut ai = AiSettings::default();
+        let built = request(&ai, "Who is Nirvana?");
+        assert!(built.prompt.starts_with("Who is Nirvana?"));
+        assert_eq!(built.max_tokens, MIN_MAX_TOKENS);
+
+        let schema = built.json_schema.unwrap().schema;
+        let properties = schema["properties"].as_object().unwrap();
+        assert_eq!(schema["required"].as_array().unwrap().len(), properties.len());
+        assert_eq!(schema["additionalProperties"], false);
+
+        // A larger configured limit is kept
+        ai.artist_info.max_tokens = Some(1500);
+        assert_eq!(request(&ai, "").max_tokens, 1500);
+    }
+
+    #[test]
+    fn long_lists_are_capped() {
+        let items = (0..20).map(|i| format!("Artist {}", i)).collect();
+        assert_eq!(clean_list(items).len(), MAX_LIST_ITEMS);
+    }
+}
@@ -0,0 +1,134 @@
+// Prompt templates for artist info and song context. Both can be replaced in settings
+// (ai.prompts) and use {artist}, {title}, {album} and {language} placeholders; the output
+// language comes from ai.language.
+
+use crate::config::AiSettings;
+
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+pub const DEFAULT_ARTIST_INFO: &str = "Provide a brief, 2-3 paragraph summary about the music artist/band '{artist}'. \
+    Include their genre, notable achievements, and impact on music. Keep it concise and informative. Write it in {language}.";
+
+pub const DEFAULT_SONG_CONTEXT: &str = "Provide a brief analysis of the song '{title}' by {artist}. \
+    Focus on its themes, meaning, and musical significance. Keep it to 2-3 paragraphs. Write it in {language}.";
+
+pub const PLACEHOLDERS: [&str; 4] = ["artist", "title", "album", "language"];
+
+// Artist info is cached per artist, so its template can't depend on the song
+pub const ARTIST_INFO_PLACEHOLDERS: [&str; 2] = ["artist", "language"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub text: String,
+    pub cache_key: String,
+}
+
+pub fn artist_info(ai: &AiSettings, artist: &str, base_key: String) -> Prompt {
+    let template = ai.prompts.artist_info.as_deref().unwrap_or(DEFAULT_ARTIST_INFO);
+    build(template, DEFAULT_ARTIST_INFO, ai, &[("artist", artist)], base_key)
+}
+
+pub fn song_context(ai: &AiSettings, artist: &str, title: &str, album: Option<&str>, base_key: String) -> Prompt {
+    let template = ai.prompts.song_context.as_deref().unwrap_or(DEFAULT_SONG_CONTEXT);
+    let vars = [("artist", artist), ("title", title), ("album", album.unwrap_or_default())];
+    build(template, DEFAULT_SONG_CONTEXT, ai, &vars, base_key)
+}
+
+// Fill in the template. Text generated with the default template and language keeps the
+// plain cache key, so existing entries stay valid; anything else gets a key suffix derived
+// from the template and language, so changing either doesn't serve text written for the old one.
+fn build(template: &str, default: &str, ai: &AiSettings, vars: &[(&str, &str)], base_key: String) -> Prompt {
+    let language = ai.language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+
+    let mut text = template.replace("{language}", language);
+    for (name, value) in vars {
+        text = text.replace(&format!("{{{}}}", name), value.trim());
+    }
+
+    let cache_key = if template == default && language == DEFAULT_LANGUAGE {
+        base_key
+    } else {
+        format!("{}|p{:016x}", base_key, fnv1a(&format!("{}\0{}", template, language)))
+    };
+
+    Prompt { text, cache_key }
+}
+
+// Split a cache key into the base key and the prompt variant suffix added by build, if any
+pub fn split_variant(cache_key: &str) -> (&str, Option<&str>) {
+    match cache_key.rsplit_once('|') {
+        Some((base, variant))
+            if variant.len() == 17
+                && variant.starts_with('p')
+                && variant[1..].bytes().all(|b| b.is_ascii_hexdigit()) =>
+        {
+            (base, Some(variant))
+        }
+        _ => (cache_key, None),
+    }
+}
+
+// Names of the {placeholders} used in a template
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        match rest.find(['{', '}']) {
+            Some(end) if rest.as_bytes()[end] == b'}' => {
+                names.push(&rest[..end]);
+                rest = &rest[end + 1..];
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+// FNV-1a: stable across builds, unlike std's hasher, so keys survive upgrades
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let mut ai = AiSettings::default();
+        let prompt = song_context(&ai, "Björk", "Jóga", Some("Homogenic"), "bjork|joga".to_string());
+        assert!(prompt.text.contains("'Jóga' by Björk"));
+        assert!(prompt.text.ends_with("Write it in English."));
+        assert_eq!(prompt.cache_key, "bjork|joga");
+
+        ai.prompts.song_context = Some("{title} ({album}) by {artist}, in {language}. {unknown}".to_string());
+        ai.language = Some("Icelandic".to_string());
+        let prompt = song_context(&ai, "Björk", "Jóga", None, "bjork|joga".to_string());
+        assert_eq!(prompt.text, "Jóga () by Björk, in Icelandic. {unknown}");
+    }
+
+    #[test]
+    fn cache_key_changes_with_template_and_language() {
+        let mut ai = AiSettings::default();
+        let default_key = artist_info(&ai, "Sigur Rós", "sigur ros".to_string()).cache_key;
+        assert_eq!(default_key, "sigur ros");
+
+        ai.language = Some("German".to_string());
+        let german_key = artist_info(&ai, "Sigur Rós", "sigur ros".to_string()).cache_key;
+        assert!(german_key.starts_with("sigur ros|p"));
+        assert_eq!(split_variant(&german_key), ("sigur ros", Some(&german_key[10..])));
+        assert_eq!(split_variant("bjork|joga"), ("bjork|joga", None));
+
+        ai.prompts.artist_info = Some("Two sentences on {artist} in {language}.".to_string());
+        let custom_key = artist_info(&ai, "Sigur Rós", "sigur ros".to_string()).cache_key;
+        assert_ne!(custom_key, german_key);
+        assert_eq!(custom_key, artist_info(&ai, "Sigur Rós", "sigur ros".to_string()).cache_key);
+    }
+
+    #[test]
+    fn placeholder_names_are_found() {
+        assert_eq!(placeholders("{artist} - {title} {{x}} {open"), vec!["artist", "title", "x"]);
+        assert!(placeholders("no placeholders").is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+// LLM provider abstraction. Artist info, song context, and lyrics formatting all request
+// completions through `LlmProvider`, so the backing service can be swapped without
+// touching the features. The only implementation today is `OpenAiCompatible`, which covers
+// OpenAI itself and self-hosted servers with the same API (llama.cpp, Ollama, vLLM).
+
+use crate::config::{AiFeatureSettings, AiSettings};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub use super::openai::{OpenAiCompatible, DEFAULT_BASE_URL, DEFAULT_MODEL};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct CompletionRequest {
+    pub prompt: String,
+    pub max_tokens: u32,
+    // Overrides the provider's model for this request
+    pub model: Option<String>,
+    // None leaves the sampling temperature to the server
+    pub temperature: Option<f64>,
+    pub timeout: Duration,
+    // Earlier messages of a conversation, sent before `prompt`
+    pub history: Vec<ChatMessage>,
+    // Asks for a reply matching this schema. Servers that don't support structured output
+    // may ignore it or reject the request, so callers still validate the reply.
+    pub json_schema: Option<JsonSchema>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    // Instructions and background for the whole conversation
+    System,
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSchema {
+    // Identifies the schema to the server, e.g. "artist_profile"
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+impl CompletionRequest {
+    pub fn new(prompt: String, max_tokens: u32) -> Self {
+        Self {
+            prompt,
+            max_tokens,
+            model: None,
+            temperature: None,
+            timeout: DEFAULT_TIMEOUT,
+            history: Vec::new(),
+            json_schema: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    // Model that produced the text; recorded in cache entry metadata
+    pub model: String,
+    // The reply stopped at max_tokens rather than finishing
+    pub truncated: bool,
+    pub usage: TokenUsage,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    // Rough count for servers that don't report usage: about 4 characters per token
+    pub fn estimate(prompt: &str, completion: &str) -> Self {
+        let tokens = |text: &str| text.chars().count().div_ceil(4) as u64;
+        Self {
+            prompt_tokens: tokens(prompt),
+            completion_tokens: tokens(completion),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+// Receives each piece of a streamed reply as it arrives
+pub type OnDelta<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    // Short provider name, recorded as the cache entry's provider
+    fn name(&self) -> &str;
+
+    fn model(&self) -> &str;
+
+    async fn complete(&self, request: CompletionRequest) -> Result<Completion, String>;
+
+    // Like `complete`, but passes each piece of text to `on_delta` as it is generated.
+    // Providers that can't stream deliver the whole text as one piece.
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        on_delta: &OnDelta<'_>,
+    ) -> Result<Completion, String> {
+        let completion = self.complete(request).await?;
+        on_delta(&completion.text);
+        Ok(completion)
+    }
+}
+
+// The features that call the provider, each with its own generation settings under `ai`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiFeature {
+    ArtistInfo,
+    SongContext,
+    LyricsFormatting,
+    SongChat,
+}
+
+impl AiFeature {
+    // Name used in settings and usage records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiFeature::ArtistInfo => "artist_info",
+            AiFeature::SongContext => "song_context",
+            AiFeature::LyricsFormatting => "lyrics_formatting",
+            AiFeature::SongChat => "song_chat",
+        }
+    }
+
+    fn settings(self, ai: &AiSettings) -> &AiFeatureSettings {
+        match self {
+            AiFeature::ArtistInfo => &ai.artist_info,
+            AiFeature::SongContext => &ai.song_context,
+            AiFeature::LyricsFormatting => &ai.lyrics_formatting,
+            AiFeature::SongChat => &ai.song_chat,
+        }
+    }
+
+    // Built-in defaults for settings left unset. Lyrics are long and should come back
+    // verbatim, so formatting gets more tokens, more time, and no sampling randomness.
+    fn defaults(self) -> (u32, Option<f64>, Duration) {
+        match self {
+            AiFeature::ArtistInfo | AiFeature::SongContext | AiFeature::SongChat => (500, None, DEFAULT_TIMEOUT),
+            AiFeature::LyricsFormatting => (2000, Some(0.0), Duration::from_secs(60)),
+        }
+    }
+
+    pub fn max_tokens(self, ai: &AiSettings) -> u32 {
+        self.settings(ai).max_tokens.unwrap_or(self.defaults().0)
+    }
+
+    // Build a request for `prompt` using this feature's settings
+    pub fn request(self, ai: &AiSettings, prompt: String) -> CompletionRequest {
+        let settings = self.settings(ai);
+        let (_, temperature, timeout) = self.defaults();
+        CompletionRequest {
+            prompt,
+            max_tokens: self.max_tokens(ai),
+            model: settings.model.clone(),
+            temperature: settings.temperature.or(temperature),
+            timeout: settings.timeout_secs.map(Duration::from_secs).unwrap_or(timeout),
+            history: Vec::new(),
+            json_schema: None,
+        }
+    }
+}
+
+// Build the provider described by the `ai` settings, with the key from the secrets store
+pub fn for_app(app_handle: &tauri::AppHandle, settings: &AiSettings) -> Result<Box<dyn LlmProvider>, String> {
+    let api_key = crate::config::get_openai_key(app_handle);
+
+    let provider = OpenAiCompatible::new(
+        settings.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL),
+        settings.model.as_deref().unwrap_or(DEFAULT_MODEL),
+        api_key,
+        settings.headers.clone().unwrap_or_default(),
+        crate::http::client(app_handle)?,
+    )?;
+    Ok(Box::new(provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_settings_override_defaults() {
+        let mut ai = AiSettings::default();
+        let request = AiFeature::LyricsFormatting.request(&ai, "lyrics".to_string());
+        assert_eq!(request.max_tokens, 2000);
+        assert_eq!(request.temperature, Some(0.0));
+        assert_eq!(request.timeout, Duration::from_secs(60));
+        assert_eq!(request.model, None);
+
+        ai.artist_info = AiFeatureSettings {
+            model: Some("gpt-4o".to_string()),
+            max_tokens: Some(900),
+            temperature: Some(0.7),
+            timeout_secs: Some(10),
+        };
+        let request = AiFeature::ArtistInfo.request(&ai, "artist".to_string());
+        assert_eq!(request.max_tokens, 900);
+        assert_eq!(request.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(request.temperature, Some(0.7));
+        assert_eq!(request.timeout, Duration::from_secs(10));
+
+        // Other features are unaffected
+        assert_eq!(AiFeature::SongContext.request(&ai, String::new()).max_tokens, 500);
+    }
+}
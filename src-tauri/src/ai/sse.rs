@@ -0,0 +1,63 @@
+// Decoder for server-sent events (text/event-stream), as used by streaming chat completions.
+// Only `data` fields are kept; event names, ids, retry hints and comments are ignored.
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    // Feed the next bytes of the stream. Returns the data of every event they complete;
+    // partial lines and events are kept until more bytes arrive.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        events
+    }
+
+    // The last event, if the stream ended without a blank line after it
+    pub fn finish(&mut self) -> Option<String> {
+        let mut events = self.push(b"\n\n");
+        events.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_split_across_reads_are_reassembled() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: {\"a\"").is_empty());
+        assert_eq!(decoder.push(b":1}\r\n\r\ndata: [DONE]\n"), vec!["{\"a\":1}"]);
+        assert_eq!(decoder.push(b"\n"), vec!["[DONE]"]);
+    }
+
+    #[test]
+    fn other_fields_are_ignored_and_multiline_data_is_joined() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.push(b": keep-alive\n\nevent: message\nid: 7\ndata: one\ndata:two\n\n");
+        assert_eq!(events, vec!["one\ntwo"]);
+
+        assert!(decoder.push(b"data: tail").is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some("tail"));
+        assert_eq!(decoder.finish(), None);
+    }
+}
@@ -0,0 +1,170 @@
+// Streaming AI generations. While a completion is being generated its text is sent to the
+// sidebar as `ai-chunk` events tagged with the caller's request id, and the request can be
+// cancelled (e.g. when the track changes) with `cancel_ai_request`.
+
+use super::provider::{Completion, CompletionRequest, LlmProvider};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tokio::sync::Notify;
+
+// Error returned by a cancelled request; the sidebar checks for this text
+pub const CANCELLED: &str = "AI request cancelled";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AiChunk {
+    pub request_id: String,
+    // Text generated since the previous chunk
+    pub delta: String,
+    // Set on the last event for a request, whether it finished, failed or was cancelled
+    pub done: bool,
+}
+
+// In-flight streaming requests, by request id
+#[derive(Default)]
+pub struct AiStreams {
+    active: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl AiStreams {
+    fn register(&self, request_id: &str) -> Arc<Notify> {
+        let cancel = Arc::new(Notify::new());
+        self.active.lock().unwrap().insert(request_id.to_string(), cancel.clone());
+        cancel
+    }
+
+    fn finish(&self, request_id: &str) {
+        self.active.lock().unwrap().remove(request_id);
+    }
+
+    // Cancel one request, or all of them when `request_id` is None. Returns how many were
+    // still running.
+    pub fn cancel(&self, request_id: Option<&str>) -> usize {
+        let mut active = self.active.lock().unwrap();
+        let cancelled: Vec<Arc<Notify>> = match request_id {
+            Some(id) => active.remove(id).into_iter().collect(),
+            None => active.drain().map(|(_, cancel)| cancel).collect(),
+        };
+        // notify_one stores a permit, so a request cancelled before it starts waiting still stops
+        for cancel in &cancelled {
+            cancel.notify_one();
+        }
+        cancelled.len()
+    }
+}
+
+// Complete `request`, streaming it when the caller passed a request id
+pub async fn complete(
+    app_handle: &tauri::AppHandle,
+    request_id: Option<&str>,
+    provider: &dyn LlmProvider,
+    request: CompletionRequest,
+) -> Result<Completion, String> {
+    match request_id {
+        Some(request_id) => run(app_handle, request_id, provider, request).await,
+        None => provider.complete(request).await,
+    }
+}
+
+// Run `request` as a stream, emitting its text as it arrives. Resolves to the complete
+// text, which is what callers cache.
+pub async fn run(
+    app_handle: &tauri::AppHandle,
+    request_id: &str,
+    provider: &dyn LlmProvider,
+    request: CompletionRequest,
+) -> Result<Completion, String> {
+    let on_delta = |delta: &str| emit(app_handle, request_id, delta, false);
+    let result = cancellable(app_handle, request_id, provider.complete_streaming(request, &on_delta)).await;
+
+    emit(app_handle, request_id, "", true);
+    result
+}
+
+// Await `future` under `request_id`, so cancel_ai_request can stop it. Used directly for
+// replies that aren't worth streaming, like structured JSON.
+pub async fn cancellable<T>(
+    app_handle: &tauri::AppHandle,
+    request_id: &str,
+    future: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let streams = app_handle.state::<AiStreams>();
+    let cancel = streams.register(request_id);
+    // Unregistered on drop too, for when the caller itself is cancelled (see crate::inflight)
+    let _registration = Registration { streams: &streams, request_id };
+
+    tokio::select! {
+        result = future => result,
+        _ = cancel.notified() => Err(CANCELLED.to_string()),
+    }
+}
+
+// Send a finished reply under `request_id` as if it had been streamed, ending the stream.
+// For callers that shared another caller's lookup (see crate::inflight), whose own
+// request_id saw none of the chunks.
+pub fn replay(app_handle: &tauri::AppHandle, request_id: &str, result: &Result<String, String>) {
+    if let Ok(text) = result {
+        emit(app_handle, request_id, text, false);
+    }
+    emit(app_handle, request_id, "", true);
+}
+
+struct Registration<'a> {
+    streams: &'a AiStreams,
+    request_id: &'a str,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.streams.finish(self.request_id);
+    }
+}
+
+// Emit ai-chunk, and hand it to the injected sidebar script directly as well, since the
+// remote page isn't granted the event API
+fn emit(app_handle: &tauri::AppHandle, request_id: &str, delta: &str, done: bool) {
+    let chunk = AiChunk {
+        request_id: request_id.to_string(),
+        delta: delta.to_string(),
+        done,
+    };
+
+    if let Err(e) = app_handle.emit("ai-chunk", chunk.clone()) {
+        log_error!("[Basitune] Failed to emit ai-chunk: {}", e);
+    }
+
+    if let (Some(window), Ok(json)) = (app_handle.get_webview_window("main"), serde_json::to_string(&chunk)) {
+        let _ = window.eval(format!("window.basituneAiChunk && window.basituneAiChunk({})", json));
+    }
+}
+
+// Cancel a streaming request by id, or every streaming request when no id is given
+#[tauri::command]
+pub fn cancel_ai_request(request_id: Option<String>, app: tauri::AppHandle) -> usize {
+    let cancelled = app.state::<AiStreams>().cancel(request_id.as_deref());
+    if cancelled > 0 {
+        log_debug!("[Basitune] Cancelled {} AI request(s)", cancelled);
+    }
+    cancelled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_stops_registered_requests() {
+        let streams = AiStreams::default();
+        let first = streams.register("artist-1");
+        let _second = streams.register("song-2");
+
+        assert_eq!(streams.cancel(Some("artist-1")), 1);
+        assert_eq!(streams.cancel(Some("artist-1")), 0);
+        // The permit is kept until the request waits for it
+        first.notified().await;
+
+        assert_eq!(streams.cancel(None), 1);
+        assert_eq!(streams.cancel(None), 0);
+    }
+}
@@ -0,0 +1,309 @@
+// AI token usage, recorded per day and feature with an estimated cost, and the spending
+// caps in ai.budget. Once a cap is reached, AI calls fail with a "budget exhausted" error
+// and the features serve only what is already cached. Days are UTC.
+
+use super::provider::{AiFeature, Completion, TokenUsage};
+use crate::config::{AiBudget, AiSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const USAGE_FILE_VERSION: u32 = 1;
+
+// Days of history kept in ai-usage.json
+const HISTORY_DAYS: usize = 400;
+
+pub const BUDGET_EXHAUSTED: &str = "AI budget exhausted";
+
+// OpenAI list prices in USD per million tokens (input, output), matched by model name
+// prefix; more specific names come first
+const OPENAI_PRICES: [(&str, f64, f64); 7] = [
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+    // Requests to a model with no known price; their cost isn't included
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost_usd += other.cost_usd;
+        self.unpriced_requests += other.unpriced_requests;
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UsageFile {
+    version: u32,
+    // Date (YYYY-MM-DD) -> feature -> totals
+    days: BTreeMap<String, BTreeMap<String, UsageTotals>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayUsage {
+    pub date: String,
+    pub total: UsageTotals,
+    pub features: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub today: UsageTotals,
+    pub month: UsageTotals,
+    pub daily_limit_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    pub exhausted: bool,
+    // Most recent first
+    pub days: Vec<DayUsage>,
+}
+
+pub struct UsageLedger {
+    // None keeps the ledger in memory only
+    path: Option<PathBuf>,
+    file: Mutex<UsageFile>,
+}
+
+impl UsageLedger {
+    pub fn load(path: PathBuf) -> Self {
+        let file = crate::persist::read_json::<UsageFile>(&path).unwrap_or_default();
+        Self { path: Some(path), file: Mutex::new(file) }
+    }
+
+    fn in_memory() -> Self {
+        Self { path: None, file: Mutex::new(UsageFile::default()) }
+    }
+
+    pub fn record(&self, date: &str, feature: AiFeature, usage: TokenUsage, cost_usd: Option<f64>) {
+        let mut file = self.file.lock().unwrap();
+        let totals = file
+            .days
+            .entry(date.to_string())
+            .or_default()
+            .entry(feature.as_str().to_string())
+            .or_default();
+        totals.add(&UsageTotals {
+            requests: 1,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            cost_usd: cost_usd.unwrap_or(0.0),
+            unpriced_requests: u64::from(cost_usd.is_none()),
+        });
+
+        while file.days.len() > HISTORY_DAYS {
+            file.days.pop_first();
+        }
+
+        file.version = USAGE_FILE_VERSION;
+        if let Some(path) = &self.path {
+            if let Err(e) = crate::persist::write_json(path, &*file) {
+                log_error!("[Basitune] Failed to save AI usage: {}", e);
+            }
+        }
+    }
+
+    // Totals for the days whose date starts with `prefix`: a full date, or YYYY-MM for a month
+    fn total(&self, prefix: &str) -> UsageTotals {
+        let file = self.file.lock().unwrap();
+        let mut total = UsageTotals::default();
+        for features in file.days.iter().filter(|(date, _)| date.starts_with(prefix)).map(|(_, features)| features) {
+            features.values().for_each(|totals| total.add(totals));
+        }
+        total
+    }
+
+    // Err once today's or this month's spending has reached its cap
+    pub fn check(&self, budget: &AiBudget, today: &str) -> Result<(), String> {
+        let caps = [
+            (budget.daily_limit_usd, self.total(today), "daily"),
+            (budget.monthly_limit_usd, self.total(&today[..7]), "monthly"),
+        ];
+        for (limit, spent, period) in caps {
+            if let Some(limit) = limit {
+                if spent.cost_usd >= limit {
+                    return Err(format!(
+                        "{}: ${:.2} of the ${:.2} {} limit used. Cached results are still available.",
+                        BUDGET_EXHAUSTED, spent.cost_usd, limit, period
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn report(&self, budget: &AiBudget, today: &str, days: usize) -> UsageReport {
+        let history: Vec<DayUsage> = {
+            let file = self.file.lock().unwrap();
+            file.days
+                .iter()
+                .rev()
+                .take(days)
+                .map(|(date, features)| {
+                    let mut total = UsageTotals::default();
+                    features.values().for_each(|totals| total.add(totals));
+                    DayUsage { date: date.clone(), total, features: features.clone() }
+                })
+                .collect()
+        };
+
+        UsageReport {
+            today: self.total(today),
+            month: self.total(&today[..7]),
+            daily_limit_usd: budget.daily_limit_usd,
+            monthly_limit_usd: budget.monthly_limit_usd,
+            exhausted: self.check(budget, today).is_err(),
+            days: history,
+        }
+    }
+}
+
+// Price per million tokens (input, output). Prices set in ai.budget win; otherwise OpenAI's
+// models use the list prices above and self-hosted servers are free. None if unknown.
+pub fn price(provider_name: &str, model: &str, budget: &AiBudget) -> Option<(f64, f64)> {
+    if let (Some(input), Some(output)) = (budget.input_price_per_million, budget.output_price_per_million) {
+        return Some((input, output));
+    }
+    if provider_name != "openai" {
+        return Some((0.0, 0.0));
+    }
+    OPENAI_PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|(_, input, output)| (*input, *output))
+}
+
+pub fn cost(usage: TokenUsage, (input, output): (f64, f64)) -> f64 {
+    (usage.prompt_tokens as f64 * input + usage.completion_tokens as f64 * output) / 1_000_000.0
+}
+
+// Today's date in UTC, as YYYY-MM-DD
+pub fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    date_from_days((secs / 86_400) as i64)
+}
+
+// Civil date for a count of days since 1970-01-01 (Howard Hinnant's civil_from_days)
+fn date_from_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Fail with a "budget exhausted" error if a spending cap has been reached
+pub fn check_budget(app_handle: &tauri::AppHandle, settings: &AiSettings) -> Result<(), String> {
+    match app_handle.try_state::<UsageLedger>() {
+        Some(ledger) => ledger.check(&settings.budget, &today()),
+        None => Ok(()),
+    }
+}
+
+pub fn record(app_handle: &tauri::AppHandle, feature: AiFeature, provider_name: &str, settings: &AiSettings, completion: &Completion) {
+    let Some(ledger) = app_handle.try_state::<UsageLedger>() else {
+        return;
+    };
+    let cost_usd = price(provider_name, &completion.model, &settings.budget).map(|price| cost(completion.usage, price));
+    ledger.record(&today(), feature, completion.usage, cost_usd);
+}
+
+// Usage for today, this month, and the last `days` days that had any (default 30)
+#[tauri::command]
+pub fn get_ai_usage(days: Option<u32>, app: tauri::AppHandle) -> UsageReport {
+    let budget = crate::config::load_config(&app).ai.budget;
+    let days = days.unwrap_or(30) as usize;
+    match app.try_state::<UsageLedger>() {
+        Some(ledger) => ledger.report(&budget, &today(), days),
+        None => UsageLedger::in_memory().report(&budget, &today(), days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> TokenUsage {
+        TokenUsage { prompt_tokens, completion_tokens }
+    }
+
+    #[test]
+    fn usage_is_totalled_per_day_feature_and_month() {
+        let ledger = UsageLedger::in_memory();
+        ledger.record("2026-03-31", AiFeature::ArtistInfo, usage(100, 50), Some(0.01));
+        ledger.record("2026-04-01", AiFeature::ArtistInfo, usage(200, 100), Some(0.02));
+        ledger.record("2026-04-01", AiFeature::SongContext, usage(10, 5), None);
+        ledger.record("2026-04-02", AiFeature::LyricsFormatting, usage(1000, 900), Some(0.5));
+
+        let today = ledger.total("2026-04-01");
+        assert_eq!((today.requests, today.prompt_tokens, today.completion_tokens), (2, 210, 105));
+        assert_eq!(today.unpriced_requests, 1);
+        assert!((ledger.total("2026-04").cost_usd - 0.52).abs() < 1e-9);
+
+        let report = ledger.report(&AiBudget::default(), "2026-04-02", 2);
+        assert_eq!(report.days.iter().map(|d| d.date.as_str()).collect::<Vec<_>>(), vec!["2026-04-02", "2026-04-01"]);
+        assert_eq!(report.days[1].features["song_context"].requests, 1);
+        assert!(!report.exhausted);
+    }
+
+    #[test]
+    fn caps_stop_requests_once_reached() {
+        let ledger = UsageLedger::in_memory();
+        let mut budget = AiBudget { daily_limit_usd: Some(0.10), ..Default::default() };
+        assert!(ledger.check(&budget, "2026-04-01").is_ok());
+
+        ledger.record("2026-04-01", AiFeature::ArtistInfo, usage(0, 0), Some(0.10));
+        let err = ledger.check(&budget, "2026-04-01").unwrap_err();
+        assert!(err.starts_with(BUDGET_EXHAUSTED) && err.contains("daily"), "{}", err);
+        // A new day starts fresh
+        assert!(ledger.check(&budget, "2026-04-02").is_ok());
+
+        budget.monthly_limit_usd = Some(0.05);
+        let err = ledger.check(&budget, "2026-04-02").unwrap_err();
+        assert!(err.contains("monthly"), "{}", err);
+        assert!(ledger.check(&budget, "2026-05-01").is_ok());
+    }
+
+    #[test]
+    fn prices_come_from_settings_or_the_list() {
+        let budget = AiBudget::default();
+        assert_eq!(price("openai", "gpt-4o-mini-2024-07-18", &budget), Some((0.15, 0.60)));
+        assert_eq!(price("openai", "gpt-4o", &budget), Some((2.50, 10.00)));
+        assert_eq!(price("openai", "o9-preview", &budget), None);
+        assert_eq!(price("openai-compatible", "llama3", &budget), Some((0.0, 0.0)));
+
+        let budget = AiBudget { input_price_per_million: Some(1.0), output_price_per_million: Some(2.0), ..Default::default() };
+        assert_eq!(price("openai-compatible", "llama3", &budget), Some((1.0, 2.0)));
+        assert!((cost(usage(1_000_000, 500_000), (1.0, 2.0)) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dates_are_formatted_from_days() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(19_782), "2024-02-29");
+        assert_eq!(date_from_days(20_454), "2026-01-01");
+        assert_eq!(today().len(), 10);
+    }
+}
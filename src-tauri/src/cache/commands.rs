@@ -0,0 +1,247 @@
+// Tauri commands for inspecting and managing the content cache from the settings UI
+
+use super::{CacheCategory, CacheEntry, CacheStats, ContentCache, EntryMetadata};
+use crate::utils::normalize_string;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+const PREVIEW_CHARS: usize = 120;
+
+#[derive(Debug, Serialize)]
+pub struct CacheEntrySummary {
+    pub key: String,
+    pub artist: String,
+    pub title: Option<String>,
+    // Prompt variant suffix ("p" + hash) for AI text written with a custom prompt or language
+    pub variant: Option<String>,
+    pub preview: String,
+    pub size_bytes: i64,
+    pub created_at: i64,
+    pub last_accessed: i64,
+    pub metadata: EntryMetadata,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheEntryPage {
+    pub category: CacheCategory,
+    pub total: usize,
+    pub offset: usize,
+    pub entries: Vec<CacheEntrySummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheEntryDetail {
+    pub category: CacheCategory,
+    pub key: String,
+    pub artist: String,
+    pub title: Option<String>,
+    pub variant: Option<String>,
+    pub size_bytes: i64,
+    #[serde(flatten)]
+    pub entry: CacheEntry,
+}
+
+// Cache keys are "artist" for artist info and "artist|title" for song context and lyrics.
+// AI text written with a custom prompt or language has a "|p<hash>" variant suffix on top.
+fn split_key(key: &str) -> (String, Option<String>, Option<String>) {
+    let (base, variant) = crate::ai::prompts::split_variant(key);
+    let variant = variant.map(str::to_string);
+    match base.split_once('|') {
+        Some((artist, title)) => (artist.to_string(), Some(title.to_string()), variant),
+        None => (base.to_string(), None, variant),
+    }
+}
+
+fn summarize(key: String, entry: &CacheEntry) -> CacheEntrySummary {
+    let (artist, title, variant) = split_key(&key);
+    let mut preview: String = entry.value.chars().take(PREVIEW_CHARS).collect();
+    if entry.value.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+
+    CacheEntrySummary {
+        size_bytes: entry.size_bytes(&key),
+        artist,
+        title,
+        variant,
+        preview,
+        created_at: entry.created_at,
+        last_accessed: entry.last_accessed,
+        metadata: entry.metadata.clone(),
+        key,
+    }
+}
+
+// Selects entries by provenance, e.g. all lyrics from scraper versions below 2 or
+// everything generated by a retired model. Every field that is set must match.
+#[derive(Debug, Default, Deserialize)]
+pub struct CacheFilter {
+    pub category: Option<CacheCategory>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub scraper_version_below: Option<u32>,
+    pub schema_version_below: Option<u32>,
+}
+
+impl CacheFilter {
+    fn is_empty(&self) -> bool {
+        self.provider.is_none()
+            && self.model.is_none()
+            && self.scraper_version_below.is_none()
+            && self.schema_version_below.is_none()
+    }
+
+    // User data such as lyrics pins is only touched by a filter that names its category
+    fn matches(&self, category: CacheCategory, entry: &CacheEntry) -> bool {
+        let meta = &entry.metadata;
+        self.category.map_or(!category.is_user_data(), |c| c == category)
+            && self.provider.as_ref().is_none_or(|p| meta.provider.as_ref() == Some(p))
+            && self.model.as_ref().is_none_or(|m| meta.model.as_ref() == Some(m))
+            // Entries without a scraper version predate versioning, so they count as older
+            && self.scraper_version_below.is_none_or(|v| meta.scraper_version.unwrap_or(0) < v)
+            && self.schema_version_below.is_none_or(|v| meta.schema_version < v)
+    }
+}
+
+#[tauri::command]
+pub fn list_cache_entries(
+    category: CacheCategory,
+    search: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    cache: tauri::State<ContentCache>,
+) -> Result<CacheEntryPage, String> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    // Search matches against the normalized key, so "Queensrÿche" finds "queensryche|..."
+    let needle = search
+        .map(|s| normalize_string(&s))
+        .filter(|s| !s.is_empty());
+
+    let mut entries: Vec<(String, CacheEntry)> = cache
+        .entries(category)
+        .into_iter()
+        .filter(|(key, _)| needle.as_ref().is_none_or(|needle| key.contains(needle.as_str())))
+        .collect();
+
+    // Most recently used first
+    entries.sort_by(|(a_key, a), (b_key, b)| {
+        b.last_accessed.cmp(&a.last_accessed).then_with(|| a_key.cmp(b_key))
+    });
+
+    let total = entries.len();
+    let page = entries
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(key, entry)| summarize(key, &entry))
+        .collect();
+
+    Ok(CacheEntryPage {
+        category,
+        total,
+        offset,
+        entries: page,
+    })
+}
+
+#[tauri::command]
+pub fn get_cache_entry(
+    category: CacheCategory,
+    key: String,
+    cache: tauri::State<ContentCache>,
+) -> Result<Option<CacheEntryDetail>, String> {
+    Ok(cache.peek(category, &key).map(|entry| {
+        let (artist, title, variant) = split_key(&key);
+        CacheEntryDetail {
+            category,
+            size_bytes: entry.size_bytes(&key),
+            artist,
+            title,
+            variant,
+            key,
+            entry,
+        }
+    }))
+}
+
+#[tauri::command]
+pub fn delete_cache_entries(
+    category: CacheCategory,
+    keys: Vec<String>,
+    cache: tauri::State<ContentCache>,
+) -> Result<usize, String> {
+    let removed = keys.iter().filter(|key| cache.remove(category, key)).count();
+    cache.flush();
+    Ok(removed)
+}
+
+#[tauri::command]
+pub fn purge_cache_category(category: CacheCategory, cache: tauri::State<ContentCache>) -> Result<usize, String> {
+    let removed = cache.purge(category);
+    log_info!("[Basitune] Purged {} {} cache entries", removed, category.as_str());
+    cache.flush();
+    Ok(removed)
+}
+
+// Drop entries matching a provenance filter so they are re-fetched on next lookup
+#[tauri::command]
+pub fn invalidate_cache_entries(filter: CacheFilter, cache: tauri::State<ContentCache>) -> Result<usize, String> {
+    if filter.is_empty() {
+        return Err("Filter must match on provider, model, or version; use purge_cache_category to clear a whole category".to_string());
+    }
+
+    let removed = cache.remove_where(|category, entry| filter.matches(category, entry));
+    log_info!("[Basitune] Invalidated {} cache entries", removed);
+    cache.flush();
+    Ok(removed)
+}
+
+#[tauri::command]
+pub fn get_cache_stats(cache: tauri::State<ContentCache>) -> Result<CacheStats, String> {
+    Ok(cache.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_split_into_artist_title_and_variant() {
+        assert_eq!(split_key("nirvana"), ("nirvana".to_string(), None, None));
+        assert_eq!(split_key("nirvana|lithium"), ("nirvana".to_string(), Some("lithium".to_string()), None));
+        assert_eq!(
+            split_key("nirvana|p00ff00ff00ff00ff"),
+            ("nirvana".to_string(), None, Some("p00ff00ff00ff00ff".to_string()))
+        );
+        assert_eq!(
+            split_key("nirvana|lithium|p00ff00ff00ff00ff"),
+            ("nirvana".to_string(), Some("lithium".to_string()), Some("p00ff00ff00ff00ff".to_string()))
+        );
+        // A title that merely starts with "p" is still a title
+        assert_eq!(split_key("nirvana|polly"), ("nirvana".to_string(), Some("polly".to_string()), None));
+    }
+
+    #[test]
+    fn filters_leave_user_data_alone_unless_named() {
+        let entry = CacheEntry {
+            value: String::new(),
+            created_at: 0,
+            last_accessed: 0,
+            metadata: EntryMetadata::new("user"),
+        };
+
+        let any_category = CacheFilter { provider: Some("user".to_string()), ..Default::default() };
+        assert!(any_category.matches(CacheCategory::Lyrics, &entry));
+        assert!(!any_category.matches(CacheCategory::LyricsPin, &entry));
+
+        let old_entries = CacheFilter { schema_version_below: Some(99), ..Default::default() };
+        assert!(!old_entries.matches(CacheCategory::LyricsPin, &entry));
+
+        let pins = CacheFilter { category: Some(CacheCategory::LyricsPin), ..any_category };
+        assert!(pins.matches(CacheCategory::LyricsPin, &entry));
+        assert!(!pins.matches(CacheCategory::Lyrics, &entry));
+    }
+}
@@ -0,0 +1,727 @@
+pub mod commands;
+mod store;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+// How often batched cache changes are written to disk
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+// Layout of the legacy content-cache.json file, kept so existing caches can be migrated
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CachedData {
+    pub artist_info: HashMap<String, String>,
+    pub song_context: HashMap<String, String>,
+    pub lyrics: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheCategory {
+    ArtistInfo,
+    // Structured artist profiles, stored as ArtistProfile JSON
+    ArtistProfile,
+    SongContext,
+    Lyrics,
+    // Failed lyrics lookups; the value is a LyricsMissReason code
+    LyricsMiss,
+    // Genius URLs the user picked by hand; never expire or get evicted
+    LyricsPin,
+}
+
+impl CacheCategory {
+    pub const ALL: [CacheCategory; 6] = [
+        CacheCategory::ArtistInfo,
+        CacheCategory::ArtistProfile,
+        CacheCategory::SongContext,
+        CacheCategory::Lyrics,
+        CacheCategory::LyricsMiss,
+        CacheCategory::LyricsPin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheCategory::ArtistInfo => "artist_info",
+            CacheCategory::ArtistProfile => "artist_profile",
+            CacheCategory::SongContext => "song_context",
+            CacheCategory::Lyrics => "lyrics",
+            CacheCategory::LyricsMiss => "lyrics_miss",
+            CacheCategory::LyricsPin => "lyrics_pin",
+        }
+    }
+
+    // User choices rather than fetched content, so expiry and eviction leave them alone
+    pub fn is_user_data(&self) -> bool {
+        matches!(self, CacheCategory::LyricsPin)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.as_str() == s)
+    }
+}
+
+// Version of the entry layout written by this build. Entries migrated from the old
+// content-cache.json (bare strings with no provenance) are version 1.
+pub const ENTRY_SCHEMA_VERSION: u32 = 2;
+
+// Where a cached value came from, so entries from an old scraper or a retired model
+// can be found and re-fetched selectively
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntryMetadata {
+    pub schema_version: u32,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub source_url: Option<String>,
+    pub match_score: Option<f64>,
+    pub scraper_version: Option<u32>,
+}
+
+impl EntryMetadata {
+    pub fn new(provider: &str) -> Self {
+        Self {
+            schema_version: ENTRY_SCHEMA_VERSION,
+            provider: Some(provider.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_source(mut self, url: String, match_score: Option<f64>) -> Self {
+        self.source_url = Some(url);
+        self.match_score = match_score;
+        self
+    }
+
+    pub fn with_scraper_version(mut self, version: u32) -> Self {
+        self.scraper_version = Some(version);
+        self
+    }
+}
+
+// created_at doubles as the time the value was fetched from its provider
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntry {
+    pub value: String,
+    pub created_at: i64,
+    pub last_accessed: i64,
+    pub metadata: EntryMetadata,
+}
+
+impl CacheEntry {
+    pub fn size_bytes(&self, key: &str) -> i64 {
+        (key.len() + self.value.len()) as i64
+    }
+}
+
+// A change made in memory that has not been written to disk yet
+#[derive(Debug, Clone)]
+pub(crate) enum PendingChange {
+    Upsert(CacheEntry),
+    Touch(i64),
+    Delete,
+}
+
+// Default expiry and size limits, used when the config does not override them
+const DEFAULT_ARTIST_INFO_TTL_DAYS: u32 = 90;
+const DEFAULT_SONG_CONTEXT_TTL_DAYS: u32 = 180;
+const DEFAULT_LYRICS_TTL_DAYS: u32 = 365;
+const DEFAULT_LYRICS_MISS_TTL_HOURS: u32 = 72;
+const DEFAULT_MAX_SIZE_MB: u32 = 50;
+
+// Expiry and size limits for the content cache, resolved from ApiConfig.
+// A TTL or size of 0 means "no limit".
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    pub artist_info_ttl_days: u32,
+    pub song_context_ttl_days: u32,
+    pub lyrics_ttl_days: u32,
+    // Misses are kept much shorter than hits, since Genius may add the song later
+    pub lyrics_miss_ttl_hours: u32,
+    pub max_size_mb: u32,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            artist_info_ttl_days: DEFAULT_ARTIST_INFO_TTL_DAYS,
+            song_context_ttl_days: DEFAULT_SONG_CONTEXT_TTL_DAYS,
+            lyrics_ttl_days: DEFAULT_LYRICS_TTL_DAYS,
+            lyrics_miss_ttl_hours: DEFAULT_LYRICS_MISS_TTL_HOURS,
+            max_size_mb: DEFAULT_MAX_SIZE_MB,
+        }
+    }
+}
+
+impl CachePolicy {
+    pub fn from_config(config: &crate::config::ApiConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            artist_info_ttl_days: config.cache.artist_info_ttl_days.unwrap_or(defaults.artist_info_ttl_days),
+            song_context_ttl_days: config.cache.song_context_ttl_days.unwrap_or(defaults.song_context_ttl_days),
+            lyrics_ttl_days: config.cache.lyrics_ttl_days.unwrap_or(defaults.lyrics_ttl_days),
+            lyrics_miss_ttl_hours: config.cache.lyrics_miss_ttl_hours.unwrap_or(defaults.lyrics_miss_ttl_hours),
+            max_size_mb: config.cache.max_size_mb.unwrap_or(defaults.max_size_mb),
+        }
+    }
+
+    // Time-to-live for a category in seconds, or None if entries never expire
+    pub fn ttl_secs(&self, category: CacheCategory) -> Option<i64> {
+        let hours = match category {
+            CacheCategory::ArtistInfo | CacheCategory::ArtistProfile => self.artist_info_ttl_days as i64 * 24,
+            CacheCategory::SongContext => self.song_context_ttl_days as i64 * 24,
+            CacheCategory::Lyrics => self.lyrics_ttl_days as i64 * 24,
+            CacheCategory::LyricsMiss => self.lyrics_miss_ttl_hours as i64,
+            CacheCategory::LyricsPin => 0,
+        };
+        if hours == 0 { None } else { Some(hours * 60 * 60) }
+    }
+
+    pub fn max_size_bytes(&self) -> Option<i64> {
+        if self.max_size_mb == 0 { None } else { Some(self.max_size_mb as i64 * 1024 * 1024) }
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+pub fn get_cache_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    crate::profiles::data_dir(app_handle)
+        .join("content-cache.db")
+}
+
+fn get_legacy_cache_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    crate::profiles::data_dir(app_handle)
+        .join("content-cache.json")
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<CacheCategory, HashMap<String, CacheEntry>>,
+    pending: HashMap<(CacheCategory, String), PendingChange>,
+    size_bytes: i64,
+}
+
+impl CacheInner {
+    fn put(&mut self, category: CacheCategory, key: String, entry: CacheEntry) {
+        let added = entry.size_bytes(&key);
+        if let Some(old) = self.entries.entry(category).or_default().insert(key.clone(), entry) {
+            self.size_bytes -= old.size_bytes(&key);
+        }
+        self.size_bytes += added;
+    }
+
+    fn remove(&mut self, category: CacheCategory, key: &str) -> bool {
+        if let Some(old) = self.entries.get_mut(&category).and_then(|map| map.remove(key)) {
+            self.size_bytes -= old.size_bytes(key);
+            self.pending.insert((category, key.to_string()), PendingChange::Delete);
+            true
+        } else {
+            false
+        }
+    }
+
+    // Queue a change, folding it into any change already waiting for the same entry
+    fn queue(&mut self, category: CacheCategory, key: String, change: PendingChange) {
+        let slot = (category, key);
+        match (self.pending.get_mut(&slot), change) {
+            (Some(PendingChange::Upsert(entry)), PendingChange::Touch(at)) => entry.last_accessed = at,
+            (_, change) => {
+                self.pending.insert(slot, change);
+            }
+        }
+    }
+}
+
+// In-memory content cache registered as Tauri state. Reads never touch the disk;
+// writes are batched and flushed to the SQLite store on a timer and on shutdown.
+pub struct ContentCache {
+    inner: Mutex<CacheInner>,
+    policy: Mutex<CachePolicy>,
+    db_path: PathBuf,
+    legacy_path: PathBuf,
+    // Serializes flushes so batches reach the disk in order
+    flush_lock: Mutex<()>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryStats {
+    pub category: CacheCategory,
+    pub entries: usize,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub categories: Vec<CategoryStats>,
+    pub total_entries: usize,
+    pub total_bytes: i64,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: f64,
+}
+
+impl ContentCache {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        let policy = CachePolicy::from_config(&crate::config::load_config(app_handle));
+        let db_path = get_cache_path(app_handle);
+        let legacy_path = get_legacy_cache_path(app_handle);
+
+        let load = || store::open(&db_path, &legacy_path).and_then(|conn| store::load_all(&conn));
+        let rows = match load() {
+            Ok(rows) => rows,
+            Err(e) if store::is_corrupt(&db_path) => {
+                // Everything in the cache can be fetched again, so a damaged database is
+                // set aside and rebuilt rather than blocking the app
+                log_warn!("[Basitune] {}", e);
+                let kept = crate::persist::quarantine(&db_path);
+                for suffix in ["-wal", "-shm"] {
+                    let mut side_file = db_path.clone().into_os_string();
+                    side_file.push(suffix);
+                    let _ = std::fs::remove_file(side_file);
+                }
+                crate::persist::record_recovery(format!(
+                    "The content cache was damaged and has been reset{}",
+                    kept.map(|p| format!(". The damaged file was kept as {}", p.display())).unwrap_or_default()
+                ));
+                load().unwrap_or_else(|e| {
+                    log_error!("[Basitune] {}", e);
+                    Vec::new()
+                })
+            }
+            Err(e) => {
+                log_error!("[Basitune] {}", e);
+                Vec::new()
+            }
+        };
+
+        let mut inner = CacheInner::default();
+        for (category, key, entry) in rows {
+            inner.put(category, key, entry);
+        }
+
+        let cache = Self {
+            inner: Mutex::new(inner),
+            policy: Mutex::new(policy),
+            db_path,
+            legacy_path,
+            flush_lock: Mutex::new(()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+        cache.enforce_policy();
+        cache
+    }
+
+    // Look up a single cached value, dropping it if it has outlived its category's TTL.
+    // A hit refreshes the entry's last-access time for LRU eviction.
+    pub fn get(&self, category: CacheCategory, key: &str) -> Option<String> {
+        let policy = *self.policy.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap();
+        let now = now_secs();
+
+        let created_at = match inner.entries.get(&category).and_then(|map| map.get(key)) {
+            Some(entry) => entry.created_at,
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        if policy.ttl_secs(category).is_some_and(|ttl| now - created_at > ttl) {
+            inner.remove(category, key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        let entry = inner.entries.get_mut(&category)?.get_mut(key)?;
+        entry.last_accessed = now;
+        let value = entry.value.clone();
+        inner.queue(category, key.to_string(), PendingChange::Touch(now));
+
+        Some(value)
+    }
+
+    // Insert or replace a single cached value, then enforce expiry and the size limit
+    pub fn insert(&self, category: CacheCategory, key: String, value: String, metadata: EntryMetadata) {
+        let now = now_secs();
+        let entry = CacheEntry {
+            value,
+            created_at: now,
+            last_accessed: now,
+            metadata,
+        };
+
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.put(category, key.clone(), entry.clone());
+            inner.queue(category, key, PendingChange::Upsert(entry));
+        }
+
+        self.enforce_policy();
+    }
+
+    // Read an entry without refreshing its access time or counting a hit, for inspection and
+    // for lookups that are not a user-facing cache hit. Expired entries read as missing but
+    // are left for get and enforce_policy to remove.
+    pub fn peek(&self, category: CacheCategory, key: &str) -> Option<CacheEntry> {
+        let policy = *self.policy.lock().unwrap();
+        let inner = self.inner.lock().unwrap();
+        let entry = inner.entries.get(&category)?.get(key)?;
+        if policy.ttl_secs(category).is_some_and(|ttl| now_secs() - entry.created_at > ttl) {
+            return None;
+        }
+        Some(entry.clone())
+    }
+
+    // Copy of every entry in a category, for listing and searching
+    pub fn entries(&self, category: CacheCategory) -> Vec<(String, CacheEntry)> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .entries
+            .get(&category)
+            .map(|map| map.iter().map(|(key, entry)| (key.clone(), entry.clone())).collect())
+            .unwrap_or_default()
+    }
+
+    // Remove every entry the predicate selects. Returns how many were removed.
+    pub fn remove_where<F>(&self, predicate: F) -> usize
+    where
+        F: Fn(CacheCategory, &CacheEntry) -> bool,
+    {
+        let predicate = &predicate;
+        let mut inner = self.inner.lock().unwrap();
+        let doomed: Vec<(CacheCategory, String)> = inner
+            .entries
+            .iter()
+            .flat_map(|(category, map)| {
+                map.iter()
+                    .filter(move |(_, entry)| predicate(*category, entry))
+                    .map(move |(key, _)| (*category, key.clone()))
+            })
+            .collect();
+        for (category, key) in &doomed {
+            inner.remove(*category, key);
+        }
+        doomed.len()
+    }
+
+    pub fn remove(&self, category: CacheCategory, key: &str) -> bool {
+        self.inner.lock().unwrap().remove(category, key)
+    }
+
+    // Remove every entry in a category. Returns how many were removed.
+    pub fn purge(&self, category: CacheCategory) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let keys: Vec<String> = inner
+            .entries
+            .get(&category)
+            .map(|map| map.keys().cloned().collect())
+            .unwrap_or_default();
+        for key in &keys {
+            inner.remove(category, key);
+        }
+        keys.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap();
+        let categories: Vec<CategoryStats> = CacheCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let map = inner.entries.get(&category);
+                CategoryStats {
+                    category,
+                    entries: map.map(|m| m.len()).unwrap_or(0),
+                    bytes: map
+                        .map(|m| m.iter().map(|(key, entry)| entry.size_bytes(key)).sum())
+                        .unwrap_or(0),
+                }
+            })
+            .collect();
+
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        CacheStats {
+            total_entries: categories.iter().map(|c| c.entries).sum(),
+            total_bytes: inner.size_bytes,
+            categories,
+            hits,
+            misses,
+            hit_ratio: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+        }
+    }
+
+    pub fn set_policy(&self, policy: CachePolicy) {
+        *self.policy.lock().unwrap() = policy;
+        self.enforce_policy();
+    }
+
+    // Remove expired entries, then evict least-recently-used entries until the cache fits max_size
+    fn enforce_policy(&self) {
+        let policy = *self.policy.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap();
+        let now = now_secs();
+
+        let mut expired: Vec<(CacheCategory, String)> = Vec::new();
+        for (category, map) in &inner.entries {
+            if let Some(ttl) = policy.ttl_secs(*category) {
+                expired.extend(
+                    map.iter()
+                        .filter(|(_, entry)| now - entry.created_at > ttl)
+                        .map(|(key, _)| (*category, key.clone())),
+                );
+            }
+        }
+        for (category, key) in &expired {
+            inner.remove(*category, key);
+        }
+
+        if let Some(max_bytes) = policy.max_size_bytes() {
+            if inner.size_bytes > max_bytes {
+                let mut by_age: Vec<(i64, CacheCategory, String)> = inner
+                    .entries
+                    .iter()
+                    .filter(|(category, _)| !category.is_user_data())
+                    .flat_map(|(category, map)| {
+                        map.iter().map(move |(key, entry)| (entry.last_accessed, *category, key.clone()))
+                    })
+                    .collect();
+                by_age.sort_by_key(|(last_accessed, _, _)| *last_accessed);
+
+                let mut evicted = 0;
+                for (_, category, key) in by_age {
+                    if inner.size_bytes <= max_bytes {
+                        break;
+                    }
+                    inner.remove(category, &key);
+                    evicted += 1;
+                }
+                log_info!("[Basitune] Evicted {} least-recently-used cache entries", evicted);
+            }
+        }
+    }
+
+    // Write all pending changes to disk in one transaction
+    pub fn flush(&self) {
+        let _flush = self.flush_lock.lock().unwrap();
+
+        let changes: Vec<_> = self.inner.lock().unwrap().pending.drain().collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let result = store::open(&self.db_path, &self.legacy_path)
+            .and_then(|mut conn| store::apply(&mut conn, &changes));
+
+        if let Err(e) = result {
+            log_error!("[Basitune] Failed to flush cache, will retry: {}", e);
+            // Put the batch back without overwriting anything queued since
+            let mut inner = self.inner.lock().unwrap();
+            for ((category, key), change) in changes {
+                let newer = inner.pending.remove(&(category, key.clone()));
+                inner.queue(category, key.clone(), change);
+                if let Some(newer) = newer {
+                    inner.queue(category, key, newer);
+                }
+            }
+        }
+    }
+
+    // Periodically flush batched changes in the background
+    pub fn start_flush_task(app_handle: tauri::AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                let app = app_handle.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || {
+                    app.state::<ContentCache>().flush();
+                })
+                .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn cache_with(policy: CachePolicy) -> ContentCache {
+        ContentCache {
+            inner: Mutex::new(CacheInner::default()),
+            policy: Mutex::new(policy),
+            db_path: PathBuf::new(),
+            legacy_path: PathBuf::new(),
+            flush_lock: Mutex::new(()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // An entry fetched `age` seconds ago and last read `idle` seconds ago
+    fn entry(value: &str, age: i64, idle: i64) -> CacheEntry {
+        let now = now_secs();
+        CacheEntry {
+            value: value.to_string(),
+            created_at: now - age,
+            last_accessed: now - idle,
+            metadata: EntryMetadata::new("test"),
+        }
+    }
+
+    fn put(cache: &ContentCache, category: CacheCategory, key: &str, entry: CacheEntry) {
+        cache.inner.lock().unwrap().put(category, key.to_string(), entry);
+    }
+
+    #[test]
+    fn ttl_depends_on_category() {
+        let policy = CachePolicy::default();
+        assert_eq!(policy.ttl_secs(CacheCategory::ArtistInfo), Some(90 * DAY));
+        assert_eq!(policy.ttl_secs(CacheCategory::ArtistProfile), Some(90 * DAY));
+        assert_eq!(policy.ttl_secs(CacheCategory::SongContext), Some(180 * DAY));
+        assert_eq!(policy.ttl_secs(CacheCategory::Lyrics), Some(365 * DAY));
+        assert_eq!(policy.ttl_secs(CacheCategory::LyricsMiss), Some(72 * 60 * 60));
+        assert_eq!(policy.ttl_secs(CacheCategory::LyricsPin), None);
+
+        let unlimited = CachePolicy { lyrics_ttl_days: 0, max_size_mb: 0, ..Default::default() };
+        assert_eq!(unlimited.ttl_secs(CacheCategory::Lyrics), None);
+        assert_eq!(unlimited.max_size_bytes(), None);
+
+        let mut config = crate::config::ApiConfig::default();
+        config.cache.song_context_ttl_days = Some(7);
+        let configured = CachePolicy::from_config(&config);
+        assert_eq!(configured.ttl_secs(CacheCategory::SongContext), Some(7 * DAY));
+        assert_eq!(configured.ttl_secs(CacheCategory::ArtistInfo), Some(90 * DAY));
+    }
+
+    #[test]
+    fn expired_entries_are_dropped() {
+        let cache = cache_with(CachePolicy::default());
+        put(&cache, CacheCategory::ArtistInfo, "old", entry("bio", 91 * DAY, 0));
+        put(&cache, CacheCategory::ArtistInfo, "older", entry("bio", 92 * DAY, 0));
+        put(&cache, CacheCategory::SongContext, "kept", entry("context", 91 * DAY, 0));
+        put(&cache, CacheCategory::LyricsMiss, "a|b", entry("no_results", 73 * 60 * 60, 0));
+        put(&cache, CacheCategory::LyricsPin, "a|c", entry("https://genius.com/x", 3650 * DAY, 0));
+
+        // Lookups check the TTL themselves
+        assert_eq!(cache.get(CacheCategory::ArtistInfo, "old"), None);
+        assert_eq!(cache.get(CacheCategory::SongContext, "kept").as_deref(), Some("context"));
+
+        // Everything else goes when the policy is enforced
+        cache.set_policy(CachePolicy::default());
+        assert!(cache.peek(CacheCategory::ArtistInfo, "older").is_none());
+        assert!(cache.peek(CacheCategory::LyricsMiss, "a|b").is_none());
+        assert!(cache.peek(CacheCategory::LyricsPin, "a|c").is_some(), "pins never expire");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        let inner = cache.inner.lock().unwrap();
+        assert!(matches!(inner.pending.get(&(CacheCategory::ArtistInfo, "old".to_string())), Some(PendingChange::Delete)));
+        assert!(matches!(inner.pending.get(&(CacheCategory::ArtistInfo, "older".to_string())), Some(PendingChange::Delete)));
+        assert!(matches!(inner.pending.get(&(CacheCategory::LyricsMiss, "a|b".to_string())), Some(PendingChange::Delete)));
+    }
+
+    #[test]
+    fn peeking_is_not_counted_as_a_lookup() {
+        let cache = cache_with(CachePolicy::default());
+        put(&cache, CacheCategory::Lyrics, "a|b", entry("words", 0, 100));
+        put(&cache, CacheCategory::LyricsMiss, "a|c", entry("no_results", 73 * 60 * 60, 0));
+
+        assert_eq!(cache.peek(CacheCategory::Lyrics, "a|b").map(|e| e.value).as_deref(), Some("words"));
+        assert!(cache.peek(CacheCategory::LyricsPin, "a|b").is_none());
+        assert!(cache.peek(CacheCategory::LyricsMiss, "a|c").is_none(), "expired entries read as missing");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (0, 0));
+        let inner = cache.inner.lock().unwrap();
+        assert!(inner.pending.is_empty(), "peeking neither touches nor removes entries");
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_first() {
+        let cache = cache_with(CachePolicy { max_size_mb: 0, ..Default::default() });
+        let big = "x".repeat(400 * 1024);
+        put(&cache, CacheCategory::Lyrics, "a", entry(&big, 0, 300));
+        put(&cache, CacheCategory::Lyrics, "b", entry(&big, 0, 200));
+        put(&cache, CacheCategory::ArtistInfo, "c", entry(&big, 0, 100));
+        put(&cache, CacheCategory::LyricsPin, "pin", entry("https://genius.com/x", 0, 1000));
+
+        // Reading "a" makes "b" the least recently used
+        assert!(cache.get(CacheCategory::Lyrics, "a").is_some());
+        cache.set_policy(CachePolicy { max_size_mb: 1, ..Default::default() });
+
+        assert!(cache.peek(CacheCategory::Lyrics, "b").is_none());
+        assert!(cache.peek(CacheCategory::Lyrics, "a").is_some());
+        assert!(cache.peek(CacheCategory::ArtistInfo, "c").is_some());
+        assert!(cache.peek(CacheCategory::LyricsPin, "pin").is_some(), "pins are never evicted");
+
+        let stats = cache.stats();
+        assert!(stats.total_bytes <= 1024 * 1024);
+        assert_eq!(stats.total_entries, 3);
+    }
+
+    #[test]
+    fn size_accounting_follows_every_change() {
+        let cache = cache_with(CachePolicy::default());
+        cache.insert(CacheCategory::Lyrics, "key".to_string(), "long lyrics".to_string(), EntryMetadata::new("genius"));
+        cache.insert(CacheCategory::ArtistInfo, "artist".to_string(), "bio".to_string(), EntryMetadata::new("openai"));
+        assert_eq!(cache.stats().total_bytes, ("key".len() + "long lyrics".len() + "artist".len() + "bio".len()) as i64);
+
+        // Replacing an entry counts only the new value
+        cache.insert(CacheCategory::Lyrics, "key".to_string(), "short".to_string(), EntryMetadata::new("genius"));
+        assert_eq!(cache.stats().total_bytes, ("key".len() + "short".len() + "artist".len() + "bio".len()) as i64);
+
+        assert!(cache.remove(CacheCategory::ArtistInfo, "artist"));
+        assert!(!cache.remove(CacheCategory::ArtistInfo, "artist"));
+        assert_eq!(cache.purge(CacheCategory::Lyrics), 1);
+
+        let stats = cache.stats();
+        assert_eq!((stats.total_entries, stats.total_bytes), (0, 0));
+        assert!(stats.categories.iter().all(|c| c.entries == 0 && c.bytes == 0));
+    }
+
+    #[test]
+    fn pending_changes_are_folded_per_entry() {
+        let mut inner = CacheInner::default();
+        let slot = |key: &str| (CacheCategory::Lyrics, key.to_string());
+
+        // A read after a write only moves the write's access time
+        inner.queue(CacheCategory::Lyrics, "a".to_string(), PendingChange::Upsert(entry("v", 0, 50)));
+        inner.queue(CacheCategory::Lyrics, "a".to_string(), PendingChange::Touch(42));
+        match inner.pending.get(&slot("a")) {
+            Some(PendingChange::Upsert(entry)) => assert_eq!(entry.last_accessed, 42),
+            other => panic!("expected an upsert, got {:?}", other),
+        }
+
+        // Later writes and deletes replace whatever was queued
+        inner.queue(CacheCategory::Lyrics, "a".to_string(), PendingChange::Delete);
+        assert!(matches!(inner.pending.get(&slot("a")), Some(PendingChange::Delete)));
+        inner.queue(CacheCategory::Lyrics, "a".to_string(), PendingChange::Upsert(entry("new", 0, 0)));
+        assert!(matches!(inner.pending.get(&slot("a")), Some(PendingChange::Upsert(e)) if e.value == "new"));
+
+        inner.queue(CacheCategory::Lyrics, "b".to_string(), PendingChange::Touch(1));
+        inner.queue(CacheCategory::Lyrics, "b".to_string(), PendingChange::Touch(2));
+        assert!(matches!(inner.pending.get(&slot("b")), Some(PendingChange::Touch(2))));
+        assert_eq!(inner.pending.len(), 2);
+    }
+}
@@ -0,0 +1,393 @@
+// SQLite persistence for the content cache. Only ContentCache talks to this module;
+// everything else reads and writes through the in-memory cache.
+
+use super::{CacheCategory, CacheEntry, CachedData, EntryMetadata, PendingChange};
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Bump when the table layout changes and add a step to migrate_schema
+const SCHEMA_VERSION: i32 = 3;
+
+// Open the content store, creating the schema and importing the legacy JSON cache on first use
+pub fn open(db_path: &Path, legacy_path: &Path) -> Result<Connection, String> {
+    // Ensure directory exists
+    if let Some(parent) = db_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let mut conn = Connection::open(db_path)
+        .map_err(|e| format!("Failed to open cache database: {}", e))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| format!("Failed to configure cache database: {}", e))?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+        .map_err(|e| format!("Failed to configure cache database: {}", e))?;
+
+    let created = migrate_schema(&conn)?;
+    if created {
+        migrate_legacy_json(legacy_path, &mut conn);
+    }
+
+    Ok(conn)
+}
+
+// True if the file exists but SQLite can't use it (not a database, or failed integrity check).
+// Lock contention and other transient errors don't count.
+pub fn is_corrupt(db_path: &Path) -> bool {
+    if !db_path.exists() {
+        return false;
+    }
+
+    let conn = match Connection::open(db_path) {
+        Ok(conn) => conn,
+        Err(e) => return is_corruption_error(&e),
+    };
+
+    match conn.pragma_query_value(None, "quick_check", |row| row.get::<_, String>(0)) {
+        Ok(result) => result != "ok",
+        Err(e) => is_corruption_error(&e),
+    }
+}
+
+fn is_corruption_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::NotADatabase) | Some(rusqlite::ErrorCode::DatabaseCorrupt)
+    )
+}
+
+// Bring the schema up to SCHEMA_VERSION. Returns true if the database was freshly created.
+fn migrate_schema(conn: &Connection) -> Result<bool, String> {
+    let version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read cache schema version: {}", e))?;
+
+    if version >= SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    if version < 1 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                category TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (category, key)
+            ) WITHOUT ROWID;",
+        )
+        .map_err(|e| format!("Failed to create cache schema: {}", e))?;
+    }
+
+    if version < 2 {
+        // Timestamps for expiry and LRU eviction. Existing rows start their lifetime now.
+        conn.execute_batch(&format!(
+            "ALTER TABLE entries ADD COLUMN created_at INTEGER NOT NULL DEFAULT {now};
+            ALTER TABLE entries ADD COLUMN last_accessed INTEGER NOT NULL DEFAULT {now};
+            CREATE INDEX IF NOT EXISTS entries_last_accessed ON entries (last_accessed);",
+            now = super::now_secs()
+        ))
+        .map_err(|e| format!("Failed to migrate cache schema: {}", e))?;
+    }
+
+    if version < 3 {
+        // Provenance metadata. Rows written before this version are entry version 1 and
+        // only their provider can be inferred, from the category.
+        conn.execute_batch(
+            "ALTER TABLE entries ADD COLUMN entry_version INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE entries ADD COLUMN provider TEXT;
+            ALTER TABLE entries ADD COLUMN model TEXT;
+            ALTER TABLE entries ADD COLUMN source_url TEXT;
+            ALTER TABLE entries ADD COLUMN match_score REAL;
+            ALTER TABLE entries ADD COLUMN scraper_version INTEGER;
+            UPDATE entries SET provider = 'openai' WHERE category IN ('artist_info', 'song_context');
+            UPDATE entries SET provider = 'genius' WHERE category = 'lyrics';",
+        )
+        .map_err(|e| format!("Failed to migrate cache schema: {}", e))?;
+    }
+
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(|e| format!("Failed to update cache schema version: {}", e))?;
+
+    Ok(version == 0)
+}
+
+// One-time import of the old content-cache.json into the database.
+// The JSON file is renamed afterwards so it is never imported twice.
+fn migrate_legacy_json(legacy_path: &Path, conn: &mut Connection) {
+    let contents = match fs::read_to_string(legacy_path) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    let legacy = match serde_json::from_str::<CachedData>(&contents) {
+        Ok(legacy) => legacy,
+        Err(e) => {
+            log_error!("[Basitune] Failed to parse legacy cache, skipping migration: {}", e);
+            return;
+        }
+    };
+
+    let now = super::now_secs();
+    let result = (|| -> rusqlite::Result<usize> {
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO entries
+                    (category, key, value, created_at, last_accessed, entry_version, provider)
+                 VALUES (?1, ?2, ?3, ?4, ?4, 1, ?5)",
+            )?;
+            let maps = [
+                (CacheCategory::ArtistInfo, &legacy.artist_info),
+                (CacheCategory::SongContext, &legacy.song_context),
+                (CacheCategory::Lyrics, &legacy.lyrics),
+            ];
+            for (category, map) in maps {
+                for (key, value) in map {
+                    // Skip empty values left behind by earlier API failures
+                    if value.trim().is_empty() {
+                        continue;
+                    }
+                    stmt.execute(params![category.as_str(), key, value, now, legacy_provider(category)])?;
+                    imported += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    })();
+
+    match result {
+        Ok(imported) => {
+            log_info!("[Basitune] Migrated {} cache entries from content-cache.json", imported);
+            let _ = fs::rename(legacy_path, legacy_path.with_extension("json.migrated"));
+        }
+        Err(e) => log_error!("[Basitune] Failed to migrate legacy cache: {}", e),
+    }
+}
+
+// Legacy entries carry no metadata; the category tells us which service produced them
+fn legacy_provider(category: CacheCategory) -> &'static str {
+    match category {
+        CacheCategory::ArtistInfo | CacheCategory::ArtistProfile | CacheCategory::SongContext => "openai",
+        CacheCategory::Lyrics | CacheCategory::LyricsMiss => "genius",
+        CacheCategory::LyricsPin => "user",
+    }
+}
+
+// Read every entry so the cache can serve lookups from memory
+pub fn load_all(conn: &Connection) -> Result<Vec<(CacheCategory, String, CacheEntry)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT category, key, value, created_at, last_accessed,
+                    entry_version, provider, model, source_url, match_score, scraper_version
+             FROM entries",
+        )
+        .map_err(|e| format!("Failed to read cache: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                CacheEntry {
+                    value: row.get(2)?,
+                    created_at: row.get(3)?,
+                    last_accessed: row.get(4)?,
+                    metadata: EntryMetadata {
+                        schema_version: row.get(5)?,
+                        provider: row.get(6)?,
+                        model: row.get(7)?,
+                        source_url: row.get(8)?,
+                        match_score: row.get(9)?,
+                        scraper_version: row.get(10)?,
+                    },
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read cache: {}", e))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (category, key, entry) = row.map_err(|e| format!("Failed to read cache entry: {}", e))?;
+        // Rows from categories this build doesn't know about are left alone
+        if let Some(category) = CacheCategory::parse(&category) {
+            entries.push((category, key, entry));
+        }
+    }
+
+    Ok(entries)
+}
+
+// Write a batch of pending changes in a single transaction
+pub fn apply(conn: &mut Connection, changes: &[((CacheCategory, String), PendingChange)]) -> Result<(), String> {
+    let result = (|| -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(
+                "INSERT OR REPLACE INTO entries
+                    (category, key, value, created_at, last_accessed,
+                     entry_version, provider, model, source_url, match_score, scraper_version)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            let mut touch = tx.prepare(
+                "UPDATE entries SET last_accessed = ?3 WHERE category = ?1 AND key = ?2",
+            )?;
+            let mut delete = tx.prepare("DELETE FROM entries WHERE category = ?1 AND key = ?2")?;
+
+            for ((category, key), change) in changes {
+                match change {
+                    PendingChange::Upsert(entry) => {
+                        upsert.execute(params![
+                            category.as_str(),
+                            key,
+                            entry.value,
+                            entry.created_at,
+                            entry.last_accessed,
+                            entry.metadata.schema_version,
+                            entry.metadata.provider,
+                            entry.metadata.model,
+                            entry.metadata.source_url,
+                            entry.metadata.match_score,
+                            entry.metadata.scraper_version
+                        ])?;
+                    }
+                    PendingChange::Touch(last_accessed) => {
+                        touch.execute(params![category.as_str(), key, last_accessed])?;
+                    }
+                    PendingChange::Delete => {
+                        delete.execute(params![category.as_str(), key])?;
+                    }
+                }
+            }
+        }
+        tx.commit()
+    })();
+
+    result.map_err(|e| format!("Failed to write cache: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ENTRY_SCHEMA_VERSION;
+
+    fn user_version(conn: &Connection) -> i32 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+    }
+
+    fn entry(value: &str, at: i64, metadata: EntryMetadata) -> CacheEntry {
+        CacheEntry { value: value.to_string(), created_at: at, last_accessed: at, metadata }
+    }
+
+    #[test]
+    fn fresh_database_gets_the_current_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(migrate_schema(&conn).unwrap());
+        assert_eq!(user_version(&conn), SCHEMA_VERSION);
+
+        // Already current: nothing to do
+        assert!(!migrate_schema(&conn).unwrap());
+        assert!(load_all(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn version_1_rows_are_migrated_with_their_provider() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (
+                category TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (category, key)
+            ) WITHOUT ROWID;
+            INSERT INTO entries VALUES ('artist_info', 'nirvana', 'Grunge band');
+            INSERT INTO entries VALUES ('lyrics', 'nirvana|lithium', 'Lyrics');
+            INSERT INTO entries VALUES ('playlists', 'x', 'From a newer build');
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        assert!(!migrate_schema(&conn).unwrap());
+        assert_eq!(user_version(&conn), SCHEMA_VERSION);
+
+        let mut rows = load_all(&conn).unwrap();
+        rows.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(rows.len(), 2, "rows from unknown categories are skipped");
+
+        let (category, key, bio) = &rows[0];
+        assert_eq!((*category, key.as_str()), (CacheCategory::ArtistInfo, "nirvana"));
+        assert_eq!(bio.value, "Grunge band");
+        assert_eq!(bio.metadata.schema_version, 1);
+        assert_eq!(bio.metadata.provider.as_deref(), Some("openai"));
+        assert!(bio.created_at > 0 && bio.last_accessed > 0);
+        assert_eq!(rows[1].2.metadata.provider.as_deref(), Some("genius"));
+    }
+
+    #[test]
+    fn pending_changes_are_written() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate_schema(&conn).unwrap();
+
+        let metadata = EntryMetadata::new("genius")
+            .with_source("https://genius.com/nirvana-lithium-lyrics".to_string(), Some(0.9))
+            .with_scraper_version(1);
+        let lyrics = (CacheCategory::Lyrics, "nirvana|lithium".to_string());
+        let bio = (CacheCategory::ArtistInfo, "nirvana".to_string());
+        let missing = (CacheCategory::SongContext, "missing".to_string());
+
+        apply(&mut conn, &[
+            (lyrics.clone(), PendingChange::Upsert(entry("Lyrics", 100, metadata))),
+            (bio.clone(), PendingChange::Upsert(entry("Bio", 100, EntryMetadata::new("openai").with_model(Some("gpt-4o-mini".to_string()))))),
+        ])
+        .unwrap();
+        assert_eq!(load_all(&conn).unwrap().len(), 2);
+
+        apply(&mut conn, &[
+            (lyrics.clone(), PendingChange::Touch(500)),
+            (bio.clone(), PendingChange::Delete),
+            // Entries that were never stored are ignored
+            (missing.clone(), PendingChange::Touch(500)),
+            (missing, PendingChange::Delete),
+        ])
+        .unwrap();
+
+        let rows = load_all(&conn).unwrap();
+        assert_eq!(rows.len(), 1);
+        let (category, key, stored) = &rows[0];
+        assert_eq!((*category, key), (lyrics.0, &lyrics.1));
+        assert_eq!(stored.value, "Lyrics");
+        assert_eq!((stored.created_at, stored.last_accessed), (100, 500));
+        assert_eq!(stored.metadata.schema_version, ENTRY_SCHEMA_VERSION);
+        assert_eq!(stored.metadata.source_url.as_deref(), Some("https://genius.com/nirvana-lithium-lyrics"));
+        assert_eq!(stored.metadata.match_score, Some(0.9));
+        assert_eq!(stored.metadata.scraper_version, Some(1));
+    }
+
+    #[test]
+    fn legacy_json_cache_is_imported_once() {
+        let dir = std::env::temp_dir().join(format!("basitune-cache-store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("content-cache.db");
+        let legacy_path = dir.join("content-cache.json");
+        fs::write(
+            &legacy_path,
+            r#"{"artist_info": {"nirvana": "Grunge band", "blank": "  "}, "song_context": {}, "lyrics": {"nirvana|lithium": "Lyrics"}}"#,
+        )
+        .unwrap();
+
+        let conn = open(&db_path, &legacy_path).unwrap();
+        assert_eq!(load_all(&conn).unwrap().len(), 2, "blank values are skipped");
+        assert!(!legacy_path.exists());
+        assert!(dir.join("content-cache.json.migrated").exists());
+        drop(conn);
+
+        // Reopening finds the schema current and imports nothing
+        let conn = open(&db_path, &legacy_path).unwrap();
+        assert_eq!(load_all(&conn).unwrap().len(), 2);
+        drop(conn);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
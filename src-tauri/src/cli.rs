@@ -0,0 +1,146 @@
+// Command-line options, parsed before the Tauri app is built.
+
+use crate::logging::LogLevel;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: basitune [OPTIONS]
+
+Options:
+  --profile <NAME>        Open the named profile (created if it doesn't exist)
+  --data-dir <PATH>       Keep all app data under PATH instead of the default location
+  --reset-window-state    Forget the saved window size and position
+  --start-hidden          Start in the tray without showing the window
+  --safe-mode             Don't inject the sidebar, visualizer or playback scripts
+  --log-level <LEVEL>     Console output: error, warn, info (default) or debug
+  -V, --version           Print the version and exit
+  -h, --help              Print this help and exit";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CliOptions {
+    pub profile: Option<String>,
+    pub data_dir: Option<PathBuf>,
+    pub reset_window_state: bool,
+    pub start_hidden: bool,
+    pub safe_mode: bool,
+    pub log_level: Option<LogLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Run(CliOptions),
+    Version,
+    Help,
+}
+
+// Parse the arguments that follow the program name. Options take their value either as
+// the next argument or after '=' (--profile work, --profile=work).
+pub fn parse<I, S>(args: I) -> Result<CliCommand, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+
+        let mut value = |name: &str| -> Result<String, String> {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "-V" | "--version" => return Ok(CliCommand::Version),
+            "--profile" => options.profile = Some(crate::profiles::validate_name(&value(&name)?)?),
+            "--data-dir" => options.data_dir = Some(PathBuf::from(value(&name)?)),
+            "--log-level" => {
+                let level = value(&name)?;
+                options.log_level = Some(
+                    LogLevel::parse(&level)
+                        .ok_or_else(|| format!("Unknown log level '{}': use error, warn, info or debug", level))?,
+                );
+            }
+            "--reset-window-state" | "--start-hidden" | "--safe-mode" if inline_value.is_some() => {
+                return Err(format!("{} doesn't take a value", name));
+            }
+            "--reset-window-state" => options.reset_window_state = true,
+            "--start-hidden" => options.start_hidden = true,
+            "--safe-mode" => options.safe_mode = true,
+            // macOS adds a process serial number when launched from Finder
+            _ if name.starts_with("-psn_") => {}
+            _ if name.starts_with('-') => return Err(format!("Unknown option '{}'", name)),
+            _ => return Err(format!("Unexpected argument '{}'", name)),
+        }
+    }
+
+    Ok(CliCommand::Run(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> CliOptions {
+        match parse(args.iter().copied()) {
+            Ok(CliCommand::Run(options)) => options,
+            other => panic!("expected options, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_arguments_gives_defaults() {
+        assert_eq!(run(&[]), CliOptions::default());
+    }
+
+    #[test]
+    fn values_can_be_separate_or_inline() {
+        let options = run(&["--profile", "Work", "--data-dir=/tmp/basitune", "--log-level", "debug"]);
+        assert_eq!(options.profile.as_deref(), Some("work"));
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/basitune")));
+        assert_eq!(options.log_level, Some(LogLevel::Debug));
+
+        let options = run(&["--profile=kids", "--log-level=WARN"]);
+        assert_eq!(options.profile.as_deref(), Some("kids"));
+        assert_eq!(options.log_level, Some(LogLevel::Warn));
+    }
+
+    #[test]
+    fn switches_are_recognized() {
+        let options = run(&["--reset-window-state", "--start-hidden", "--safe-mode"]);
+        assert!(options.reset_window_state);
+        assert!(options.start_hidden);
+        assert!(options.safe_mode);
+    }
+
+    #[test]
+    fn help_and_version_win() {
+        assert_eq!(parse(["--safe-mode", "--version"]), Ok(CliCommand::Version));
+        assert_eq!(parse(["-V"]), Ok(CliCommand::Version));
+        assert_eq!(parse(["-h", "--bogus"]), Ok(CliCommand::Help));
+    }
+
+    #[test]
+    fn bad_input_is_rejected() {
+        assert!(parse(["--profile"]).is_err());
+        assert!(parse(["--profile="]).is_err());
+        assert!(parse(["--profile", "../etc"]).is_err());
+        assert!(parse(["--log-level", "loud"]).is_err());
+        assert!(parse(["--safe-mode=yes"]).is_err());
+        assert!(parse(["--frobnicate"]).is_err());
+        assert!(parse(["extra"]).is_err());
+    }
+
+    #[test]
+    fn macos_process_serial_number_is_ignored() {
+        assert_eq!(run(&["-psn_0_12345"]), CliOptions::default());
+    }
+}
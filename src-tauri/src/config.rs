@@ -1,24 +1,57 @@
 use serde::{Deserialize, Serialize};
-use std::fs;
+use crate::secrets::{CredentialStatus, Secret};
+use crate::validation::{FieldError, SettingsError};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tauri::Manager;
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+// Bump when the layout of config.json changes and add a step to migrate_config
+pub const CONFIG_SCHEMA_VERSION: u32 = 2;
+
+// Settings persisted in config.json, grouped by the part of the app they configure.
+// Every section defaults field by field, so a file written by an older build still loads.
+// API keys are not part of it; they live encrypted in the secrets store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ApiConfig {
-    pub openai_api_key: Option<String>,
-    pub genius_access_token: Option<String>,
+    pub schema_version: u32,
+    pub general: GeneralSettings,
+    pub visualizer: VisualizerConfig,
+    pub cache: CacheSettings,
+    pub ai: AiSettings,
+    pub network: NetworkSettings,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            general: GeneralSettings::default(),
+            visualizer: VisualizerConfig::default(),
+            cache: CacheSettings::default(),
+            ai: AiSettings::default(),
+            network: NetworkSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct GeneralSettings {
     pub close_to_tray: Option<bool>,
     pub enable_notifications: Option<bool>,
     pub resume_playback_on_startup: Option<bool>,
-    // Playback position persistence
-    pub last_song_artist: Option<String>,
-    pub last_song_title: Option<String>,
-    pub last_position_seconds: Option<f64>,
-    pub was_playing: Option<bool>,
-    // Visualizer settings
-    pub visualizer_style: Option<String>,
-    pub visualizer_color: Option<String>,
-    pub visualizer_sensitivity: Option<f64>,
+    pub enable_discord_presence: Option<bool>,
+}
+
+// Unset fields fall back to the visualizer's own defaults in the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct VisualizerConfig {
+    pub style: Option<String>,
+    pub color: Option<String>,
+    pub sensitivity: Option<f64>,
     pub color_palette: Option<String>,
     pub animation_speed: Option<f64>,
     pub glow_enabled: Option<bool>,
@@ -28,7 +61,83 @@ pub struct ApiConfig {
     pub line_thickness: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Content cache policy (TTLs in days, size in MB; 0 disables the limit)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CacheSettings {
+    pub artist_info_ttl_days: Option<u32>,
+    pub song_context_ttl_days: Option<u32>,
+    pub lyrics_ttl_days: Option<u32>,
+    pub lyrics_miss_ttl_hours: Option<u32>,
+    pub max_size_mb: Option<u32>,
+}
+
+// Connection settings shared by every outbound request (AI provider, Genius). Unset
+// `proxy` uses the HTTP_PROXY/HTTPS_PROXY/NO_PROXY environment variables; `ca_bundle` is
+// a PEM file of extra root certificates to trust, e.g. a corporate CA.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct NetworkSettings {
+    // http://, https://, socks5:// or socks5h:// URL, optionally with user:password@
+    pub proxy: Option<String>,
+    // Comma-separated hosts that bypass `proxy`, e.g. "localhost,127.0.0.1,.corp.example"
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub connect_timeout_secs: Option<u64>,
+    // For requests without a timeout of their own; AI requests use ai.<feature>.timeout_secs
+    pub timeout_secs: Option<u64>,
+}
+
+// AI provider: any server with an OpenAI-compatible chat completions API (OpenAI,
+// llama.cpp, Ollama, vLLM). Unset fields use OpenAI and gpt-4o-mini. The API key is kept
+// in the secrets store; `headers` are sent with every request and stored in plain text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AiSettings {
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    // Language the write-ups are requested in; unset means English
+    pub language: Option<String>,
+    pub prompts: PromptTemplates,
+    pub budget: AiBudget,
+    pub artist_info: AiFeatureSettings,
+    pub song_context: AiFeatureSettings,
+    pub lyrics_formatting: AiFeatureSettings,
+    pub song_chat: AiFeatureSettings,
+}
+
+// Spending caps in US dollars; unset means no cap. Costs use OpenAI's list prices for known
+// models and zero for self-hosted servers, unless prices are set here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AiBudget {
+    pub daily_limit_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    pub input_price_per_million: Option<f64>,
+    pub output_price_per_million: Option<f64>,
+}
+
+// Replacement prompts; unset fields use the built-in ones in ai::prompts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PromptTemplates {
+    pub artist_info: Option<String>,
+    pub song_context: Option<String>,
+}
+
+// Per-feature generation settings. Unset fields use the feature's built-in defaults
+// (see ai::provider::AiFeature); `model` overrides ai.model for that feature only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AiFeatureSettings {
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VisualizerSettings {
     pub style: String,
     pub color: String,
@@ -42,7 +151,7 @@ pub struct VisualizerSettings {
     pub line_thickness: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct WindowState {
     pub width: u32,
     pub height: u32,
@@ -71,7 +180,7 @@ impl Default for WindowState {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PlaybackPosition {
     pub artist: String,
     pub title: String,
@@ -80,176 +189,631 @@ pub struct PlaybackPosition {
 }
 
 pub fn get_config_path(app_handle: &tauri::AppHandle) -> PathBuf {
-    app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data dir")
+    crate::profiles::data_dir(app_handle)
         .join("config.json")
 }
 
+// Playback position lives in its own file so the frequent saves while listening
+// don't rewrite (and back up) the settings
+fn get_playback_state_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    crate::profiles::data_dir(app_handle)
+        .join("playback-state.json")
+}
+
 pub fn load_config(app_handle: &tauri::AppHandle) -> ApiConfig {
-    let config_path = get_config_path(app_handle);
-    
-    if let Ok(contents) = fs::read_to_string(&config_path) {
-        if let Ok(config) = serde_json::from_str(&contents) {
-            return config;
+    // A damaged config.json is restored from config.json.bak rather than silently
+    // replaced by defaults, which would wipe the API keys on the next save
+    let raw: Value = match crate::persist::read_json(&get_config_path(app_handle)) {
+        Some(raw) => raw,
+        None => return ApiConfig::default(),
+    };
+
+    let version = schema_version_of(&raw);
+    if version > CONFIG_SCHEMA_VERSION {
+        log_warn!(
+            "[Basitune] config.json was written by a newer version (schema {}); unknown settings will be ignored and settings can't be changed",
+            version
+        );
+    }
+
+    let (mut value, playback) = migrate_config(raw);
+    let plaintext = take_plaintext_credentials(&mut value);
+    let (mut config, mut errors) = match serde_json::from_value::<ApiConfig>(value.clone()) {
+        Ok(config) => (config, Vec::new()),
+        Err(_) => from_value_leniently(&value),
+    };
+    errors.extend(crate::validation::sanitize_config(&mut config));
+    for error in errors {
+        log_warn!("[Basitune] Ignoring invalid setting {} in config.json: {}", error.field, error.message);
+    }
+
+    // Keys written by older versions or added by hand are moved into the secrets store.
+    // If that fails the file is left as is so they aren't lost.
+    let mut scrub = false;
+    if !plaintext.is_empty() {
+        match crate::secrets::SecretStore::for_app(app_handle).set_all(&plaintext) {
+            Ok(()) => scrub = true,
+            Err(e) => {
+                log_error!("[Basitune] Failed to move API keys to the secrets store: {}", e);
+                return config;
+            }
+        }
+    }
+
+    if version < CONFIG_SCHEMA_VERSION {
+        if let Some(position) = playback {
+            let path = get_playback_state_path(app_handle);
+            if !path.exists() {
+                if let Err(e) = crate::persist::write_json(&path, &position) {
+                    log_error!("[Basitune] Failed to migrate playback state: {}", e);
+                }
+            }
+        }
+        match write_config(app_handle, &config) {
+            Ok(()) => log_info!(
+                "[Basitune] Migrated config.json from schema version {} to {}",
+                version, CONFIG_SCHEMA_VERSION
+            ),
+            Err(e) => log_error!("[Basitune] Failed to save migrated config: {}", e),
+        }
+    }
+
+    if scrub {
+        match scrub_config_files(app_handle, &config) {
+            Ok(()) => log_info!("[Basitune] Moved {} API key(s) from config.json to the secrets store", plaintext.len()),
+            Err(e) => log_error!("[Basitune] Failed to remove API keys from config.json: {}", e),
         }
     }
-    
-    ApiConfig::default()
+
+    config
 }
 
-pub fn get_genius_token(app_handle: &tauri::AppHandle) -> Option<String> {
-    // First try environment variable (for development)
-    if let Ok(token) = std::env::var("GENIUS_ACCESS_TOKEN") {
-        return Some(token);
+fn write_config(app_handle: &tauri::AppHandle, config: &ApiConfig) -> Result<(), String> {
+    ensure_writable(config)?;
+    crate::persist::write_json(&get_config_path(app_handle), config)
+        .map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+// Settings from a newer build would lose every field this one doesn't know about if
+// they were written back, so such a file is left untouched
+fn ensure_writable(config: &ApiConfig) -> Result<(), String> {
+    if config.schema_version > CONFIG_SCHEMA_VERSION {
+        return Err(format!(
+            "config.json was written by a newer version of Basitune (settings schema {}); update Basitune to change settings",
+            config.schema_version
+        ));
     }
-    
-    // Then try config file (for release builds)
-    let config = load_config(app_handle);
-    config.genius_access_token
+    Ok(())
 }
 
-pub fn get_openai_key(app_handle: &tauri::AppHandle) -> Option<String> {
-    // First try environment variable (for development)
-    if let Ok(key) = std::env::var("OPENAI_API_KEY") {
-        return Some(key);
+// Read settings field by field, keeping the default for any value of the wrong type, so
+// one bad value doesn't reset every other setting (and the next save doesn't write
+// those defaults over them)
+fn from_value_leniently(value: &Value) -> (ApiConfig, Vec<FieldError>) {
+    let mut errors = Vec::new();
+    let config = serde_json::to_value(ApiConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|template| {
+            let mut merged = template.clone();
+            patch_fields(&template, &mut merged, value, &[], &mut errors);
+            serde_json::from_value::<ApiConfig>(merged).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            errors.push(FieldError { field: "config.json".to_string(), message: e });
+            ApiConfig::default()
+        });
+    (config, errors)
+}
+
+// Re-read config.json after an outside edit. Unlike load_config, a file that doesn't parse
+// or holds values of the wrong type is reported instead of restored from backup, since the
+// user may still be in the middle of editing it.
+pub fn reload_config(app_handle: &tauri::AppHandle) -> Result<ApiConfig, String> {
+    if let Some(raw) = crate::persist::read_json_strict::<Value>(&get_config_path(app_handle))? {
+        let (mut value, _) = migrate_config(raw);
+        take_plaintext_credentials(&mut value);
+        let mut config = serde_json::from_value::<ApiConfig>(value)
+            .map_err(|e| format!("Invalid settings in config.json: {}", e))?;
+        let errors = crate::validation::sanitize_config(&mut config);
+        if !errors.is_empty() {
+            return Err(SettingsError::invalid(errors).message);
+        }
+    }
+
+    Ok(load_config(app_handle))
+}
+
+// Names of the sections that differ, as used in the config-changed event
+pub fn changed_sections(old: &ApiConfig, new: &ApiConfig) -> Vec<&'static str> {
+    let mut sections = Vec::new();
+    if old.general != new.general {
+        sections.push("general");
+    }
+    if old.visualizer != new.visualizer {
+        sections.push("visualizer");
+    }
+    if old.cache != new.cache {
+        sections.push("cache");
+    }
+    if old.ai != new.ai {
+        sections.push("ai");
+    }
+    if old.network != new.network {
+        sections.push("network");
+    }
+    sections
+}
+
+// Rewrite config.json and its backup without credentials, so no plaintext copy is left behind
+fn scrub_config_files(app_handle: &tauri::AppHandle, config: &ApiConfig) -> Result<(), String> {
+    write_config(app_handle, config)?;
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    crate::persist::write_atomic(&crate::persist::backup_path(&get_config_path(app_handle)), json.as_bytes())
+}
+
+// Remove any plaintext API keys from a raw config, returning the non-empty ones
+fn take_plaintext_credentials(value: &mut Value) -> Vec<(Secret, String)> {
+    let section = match value.as_object_mut().and_then(|map| map.remove("credentials")) {
+        Some(Value::Object(section)) => section,
+        _ => return Vec::new(),
+    };
+
+    section
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let secret = Secret::parse(&name)?;
+            let value = value.as_str()?.trim().to_string();
+            (!value.is_empty()).then_some((secret, value))
+        })
+        .collect()
+}
+
+// Files written before versioning have no schema_version and use the flat layout
+fn schema_version_of(raw: &Value) -> u32 {
+    raw.get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(1)
+}
+
+// Flat schema 1 fields and where they live in schema 2, as (old name, section, new name).
+// Credentials are grouped too, then moved out by take_plaintext_credentials.
+const V1_FIELDS: &[(&str, &str, &str)] = &[
+    ("openai_api_key", "credentials", "openai_api_key"),
+    ("genius_access_token", "credentials", "genius_access_token"),
+    ("close_to_tray", "general", "close_to_tray"),
+    ("enable_notifications", "general", "enable_notifications"),
+    ("resume_playback_on_startup", "general", "resume_playback_on_startup"),
+    ("visualizer_style", "visualizer", "style"),
+    ("visualizer_color", "visualizer", "color"),
+    ("visualizer_sensitivity", "visualizer", "sensitivity"),
+    ("color_palette", "visualizer", "color_palette"),
+    ("animation_speed", "visualizer", "animation_speed"),
+    ("glow_enabled", "visualizer", "glow_enabled"),
+    ("glow_intensity", "visualizer", "glow_intensity"),
+    ("bar_spacing", "visualizer", "bar_spacing"),
+    ("particle_count", "visualizer", "particle_count"),
+    ("line_thickness", "visualizer", "line_thickness"),
+    ("cache_artist_info_ttl_days", "cache", "artist_info_ttl_days"),
+    ("cache_song_context_ttl_days", "cache", "song_context_ttl_days"),
+    ("cache_lyrics_ttl_days", "cache", "lyrics_ttl_days"),
+    ("cache_lyrics_miss_ttl_hours", "cache", "lyrics_miss_ttl_hours"),
+    ("cache_max_size_mb", "cache", "max_size_mb"),
+];
+
+// Bring a raw config.json up to CONFIG_SCHEMA_VERSION. Also returns any playback
+// position that has to move out to playback-state.json.
+fn migrate_config(raw: Value) -> (Value, Option<PlaybackPosition>) {
+    let version = schema_version_of(&raw);
+    let mut value = raw;
+    let mut playback = None;
+
+    if version < 2 {
+        (value, playback) = migrate_v1(value);
+    }
+
+    // A newer file keeps its version, so it stays marked as not ours to rewrite
+    if let Some(map) = value.as_object_mut() {
+        map.insert("schema_version".to_string(), version.max(CONFIG_SCHEMA_VERSION).into());
+    }
+
+    (value, playback)
+}
+
+// Schema 1 -> 2: group the flat fields into sections and split off the playback state
+fn migrate_v1(raw: Value) -> (Value, Option<PlaybackPosition>) {
+    let flat = match raw {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+
+    let mut sections = Map::new();
+    for (old, section, new) in V1_FIELDS {
+        if let Some(value) = flat.get(*old).filter(|v| !v.is_null()) {
+            if let Some(fields) = sections
+                .entry(section.to_string())
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+            {
+                fields.insert(new.to_string(), value.clone());
+            }
+        }
     }
-    
-    // Then try config file (for release builds)
-    let config = load_config(app_handle);
-    config.openai_api_key
+
+    let playback = match (
+        flat.get("last_song_artist").and_then(Value::as_str),
+        flat.get("last_song_title").and_then(Value::as_str),
+        flat.get("last_position_seconds").and_then(Value::as_f64),
+        flat.get("was_playing").and_then(Value::as_bool),
+    ) {
+        (Some(artist), Some(title), Some(position_seconds), Some(was_playing)) => Some(PlaybackPosition {
+            artist: artist.to_string(),
+            title: title.to_string(),
+            position_seconds,
+            was_playing,
+        }),
+        _ => None,
+    };
+
+    (Value::Object(sections), playback)
+}
+
+// Apply a partial update of the form {"section": {"field": value}} to `current`.
+// Groups of settings inside a section (e.g. ai.artist_info) are patched the same way.
+// Unknown sections or fields, values of the wrong type, and values that fail validation
+// are rejected with every offending setting named; out-of-range numbers are clamped.
+// null clears a field, or a whole group, back to its default.
+fn apply_patch(current: &ApiConfig, patch: &Value) -> Result<ApiConfig, SettingsError> {
+    let patch = patch
+        .as_object()
+        .ok_or_else(|| SettingsError::new("Config patch must be a JSON object"))?;
+
+    let template = serde_json::to_value(ApiConfig::default()).map_err(|e| e.to_string())?;
+    let mut merged = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    let mut errors = Vec::new();
+
+    for (section, fields) in patch {
+        let message = if section == "schema_version" {
+            "managed by Basitune and cannot be changed"
+        } else if section == "credentials" {
+            "API keys are stored encrypted and can only be changed with set_api_secret"
+        } else if template.get(section).is_none() {
+            "unknown settings section"
+        } else if !fields.is_object() {
+            "must be an object"
+        } else {
+            patch_fields(&template[section], &mut merged[section], fields, &[section], &mut errors);
+            continue;
+        };
+        errors.push(FieldError { field: section.clone(), message: message.to_string() });
+    }
+
+    if !errors.is_empty() {
+        return Err(SettingsError::invalid(errors));
+    }
+
+    let mut updated: ApiConfig = serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+    let errors = crate::validation::sanitize_config(&mut updated);
+    if !errors.is_empty() {
+        return Err(SettingsError::invalid(errors));
+    }
+    Ok(updated)
+}
+
+// Merge `fields` into `target`. Groups of settings (objects in the template) are merged
+// field by field; any other value, including maps like ai.headers, is replaced whole.
+fn patch_fields(template: &Value, target: &mut Value, fields: &Value, path: &[&str], errors: &mut Vec<FieldError>) {
+    for (field, value) in fields.as_object().into_iter().flatten() {
+        let mut field_path = path.to_vec();
+        field_path.push(field);
+
+        let field_template = match template.get(field) {
+            Some(field_template) => field_template,
+            None => {
+                errors.push(FieldError { field: field_path.join("."), message: "unknown setting".to_string() });
+                continue;
+            }
+        };
+
+        if field_template.is_object() {
+            match value {
+                Value::Object(_) => patch_fields(field_template, &mut target[field], value, &field_path, errors),
+                Value::Null => target[field] = field_template.clone(),
+                _ => errors.push(FieldError { field: field_path.join("."), message: "must be an object".to_string() }),
+            }
+            continue;
+        }
+
+        // Check the value on its own so the error names the field
+        let alone = field_path.iter().rev().fold(value.clone(), |value, key| json!({ *key: value }));
+        if let Err(e) = serde_json::from_value::<ApiConfig>(alone) {
+            errors.push(FieldError { field: field_path.join("."), message: format!("wrong type: {}", e) });
+            continue;
+        }
+
+        target[field] = value.clone();
+    }
+}
+
+pub fn get_genius_token(app_handle: &tauri::AppHandle) -> Option<String> {
+    // Loading the config first imports any key still sitting in plaintext in config.json
+    load_config(app_handle);
+    crate::secrets::get_secret(app_handle, Secret::GeniusAccessToken)
+}
+
+pub fn get_openai_key(app_handle: &tauri::AppHandle) -> Option<String> {
+    load_config(app_handle);
+    crate::secrets::get_secret(app_handle, Secret::OpenaiApiKey)
+}
+
+// Settings as sent to the frontend: the config plus masked credential status, never the keys
+#[derive(Debug, Serialize)]
+pub struct ConfigView {
+    #[serde(flatten)]
+    pub config: ApiConfig,
+    pub credentials: CredentialStatus,
 }
 
 #[tauri::command]
-pub fn get_config(app: tauri::AppHandle) -> Result<ApiConfig, String> {
-    Ok(load_config(&app))
+pub fn get_config(app: tauri::AppHandle) -> Result<ConfigView, String> {
+    Ok(ConfigView {
+        config: load_config(&app),
+        credentials: crate::secrets::credential_status(&app),
+    })
 }
 
+// Update only the settings named in `patch`, e.g. {"general": {"close_to_tray": true}}.
+// The file is rewritten only if something actually changed. Returns the resulting config.
 #[tauri::command]
-pub fn save_config(app: tauri::AppHandle, openai_api_key: String, genius_access_token: String, close_to_tray: bool, enable_notifications: bool, resume_playback_on_startup: bool) -> Result<(), String> {
-    // Load existing config to preserve playback state and visualizer settings
-    let existing = load_config(&app);
-    
-    let config = ApiConfig {
-        openai_api_key: if openai_api_key.is_empty() { None } else { Some(openai_api_key) },
-        genius_access_token: if genius_access_token.is_empty() { None } else { Some(genius_access_token) },
-        close_to_tray: Some(close_to_tray),
-        enable_notifications: Some(enable_notifications),
-        resume_playback_on_startup: Some(resume_playback_on_startup),
-        // Preserve playback state
-        last_song_artist: existing.last_song_artist,
-        last_song_title: existing.last_song_title,
-        last_position_seconds: existing.last_position_seconds,
-        was_playing: existing.was_playing,
-        // Preserve visualizer settings
-        visualizer_style: existing.visualizer_style,
-        visualizer_color: existing.visualizer_color,
-        visualizer_sensitivity: existing.visualizer_sensitivity,
-        color_palette: existing.color_palette,
-        animation_speed: existing.animation_speed,
-        glow_enabled: existing.glow_enabled,
-        glow_intensity: existing.glow_intensity,
-        bar_spacing: existing.bar_spacing,
-        particle_count: existing.particle_count,
-        line_thickness: existing.line_thickness,
-    };
-    
-    let config_path = get_config_path(&app);
-    
-    // Ensure the directory exists
-    if let Some(parent) = config_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
-    }
-    
-    // Serialize and write config
-    let json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-    
-    fs::write(&config_path, json)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
-    
-    Ok(())
+pub fn update_config(app: tauri::AppHandle, patch: Value) -> Result<ApiConfig, SettingsError> {
+    let current = load_config(&app);
+    let updated = apply_patch(&current, &patch)?;
+
+    if updated == current {
+        return Ok(updated);
+    }
+
+    write_config(&app, &updated)?;
+
+    if updated.cache != current.cache {
+        if let Some(cache) = app.try_state::<crate::cache::ContentCache>() {
+            cache.set_policy(crate::cache::CachePolicy::from_config(&updated));
+        }
+    }
+
+    Ok(updated)
 }
 
+// Blank keys leave the stored ones unchanged, since the frontend only ever sees masked values
 #[tauri::command]
-pub fn save_visualizer_settings(app: tauri::AppHandle, settings: VisualizerSettings) -> Result<(), String> {
-    // Load existing config to preserve other settings
-    let mut config = load_config(&app);
-    
-    // Update visualizer settings
-    config.visualizer_style = Some(settings.style);
-    config.visualizer_color = Some(settings.color);
-    config.visualizer_sensitivity = Some(settings.sensitivity);
-    config.color_palette = Some(settings.color_palette);
-    config.animation_speed = Some(settings.animation_speed);
-    config.glow_enabled = Some(settings.glow_enabled);
-    config.glow_intensity = Some(settings.glow_intensity);
-    config.bar_spacing = Some(settings.bar_spacing);
-    config.particle_count = Some(settings.particle_count);
-    config.line_thickness = Some(settings.line_thickness);
-    
-    let config_path = get_config_path(&app);
-    
-    // Ensure the directory exists
-    if let Some(parent) = config_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
-    }
-    
-    // Serialize and write config
-    let json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-    
-    fs::write(&config_path, json)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
-    
-    Ok(())
+pub fn save_config(app: tauri::AppHandle, openai_api_key: String, genius_access_token: String, close_to_tray: bool, enable_notifications: bool, resume_playback_on_startup: bool) -> Result<(), SettingsError> {
+    let store = crate::secrets::SecretStore::for_app(&app);
+    for (secret, value) in [(Secret::OpenaiApiKey, openai_api_key), (Secret::GeniusAccessToken, genius_access_token)] {
+        if !value.trim().is_empty() {
+            store.set(secret, Some(&value))?;
+        }
+    }
+
+    let patch = json!({
+        "general": {
+            "close_to_tray": close_to_tray,
+            "enable_notifications": enable_notifications,
+            "resume_playback_on_startup": resume_playback_on_startup,
+        },
+    });
+
+    update_config(app, patch).map(|_| ())
+}
+
+#[tauri::command]
+pub fn save_visualizer_settings(app: tauri::AppHandle, settings: VisualizerSettings) -> Result<(), SettingsError> {
+    let visualizer = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize visualizer settings: {}", e))?;
+
+    update_config(app, json!({ "visualizer": visualizer })).map(|_| ())
 }
 
 #[tauri::command]
 pub fn save_playback_position(app: tauri::AppHandle, artist: String, title: String, position_seconds: f64, was_playing: bool) -> Result<(), String> {
-    // Load existing config
-    let mut config = load_config(&app);
-    
-    // Update playback state
-    config.last_song_artist = Some(artist);
-    config.last_song_title = Some(title);
-    config.last_position_seconds = Some(position_seconds);
-    config.was_playing = Some(was_playing);
-    
-    // Save config
-    let config_path = get_config_path(&app);
-    
-    if let Some(parent) = config_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
-    }
-    
-    let json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-    
-    fs::write(&config_path, json)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
-    
-    Ok(())
+    if !position_seconds.is_finite() {
+        return Err("position_seconds must be a number".to_string());
+    }
+    let position = PlaybackPosition {
+        artist,
+        title,
+        position_seconds: position_seconds.max(0.0),
+        was_playing,
+    };
+
+    crate::persist::write_json(&get_playback_state_path(&app), &position)
+        .map_err(|e| format!("Failed to write playback state: {}", e))
 }
 
 #[tauri::command]
 pub fn get_playback_position(app: tauri::AppHandle) -> Result<Option<PlaybackPosition>, String> {
-    let config = load_config(&app);
-    
-    if let (Some(artist), Some(title), Some(position), Some(was_playing)) = (
-        config.last_song_artist,
-        config.last_song_title,
-        config.last_position_seconds,
-        config.was_playing,
-    ) {
-        Ok(Some(PlaybackPosition {
-            artist,
-            title,
-            position_seconds: position,
-            was_playing,
-        }))
-    } else {
-        Ok(None)
+    Ok(crate::persist::read_json(&get_playback_state_path(&app)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_flat_v1_config_into_sections() {
+        let v1 = json!({
+            "openai_api_key": "sk-test",
+            "close_to_tray": true,
+            "visualizer_style": "bars",
+            "particle_count": 120,
+            "cache_lyrics_ttl_days": 30,
+            "genius_access_token": null,
+            "last_song_artist": "Rush",
+            "last_song_title": "Limelight",
+            "last_position_seconds": 42.5,
+            "was_playing": true
+        });
+
+        let (mut value, playback) = migrate_config(v1);
+        let credentials = take_plaintext_credentials(&mut value);
+        let config: ApiConfig = serde_json::from_value(value).unwrap();
+
+        assert_eq!(config.schema_version, CONFIG_SCHEMA_VERSION);
+        assert_eq!(credentials, vec![(Secret::OpenaiApiKey, "sk-test".to_string())]);
+        assert_eq!(config.general.close_to_tray, Some(true));
+        assert_eq!(config.visualizer.style.as_deref(), Some("bars"));
+        assert_eq!(config.visualizer.particle_count, Some(120));
+        assert_eq!(config.cache.lyrics_ttl_days, Some(30));
+
+        let playback = playback.expect("playback state should be split off");
+        assert_eq!(playback.artist, "Rush");
+        assert_eq!(playback.position_seconds, 42.5);
+    }
+
+    #[test]
+    fn hand_added_credentials_are_taken_out() {
+        let mut value = json!({
+            "schema_version": CONFIG_SCHEMA_VERSION,
+            "credentials": { "genius_access_token": " tok ", "openai_api_key": "" },
+            "general": { "close_to_tray": true }
+        });
+
+        let credentials = take_plaintext_credentials(&mut value);
+
+        assert_eq!(credentials, vec![(Secret::GeniusAccessToken, "tok".to_string())]);
+        assert!(value.get("credentials").is_none());
+    }
+
+    #[test]
+    fn current_schema_is_left_alone() {
+        let config = ApiConfig {
+            general: GeneralSettings {
+                enable_notifications: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (value, playback) = migrate_config(serde_json::to_value(&config).unwrap());
+
+        assert_eq!(serde_json::from_value::<ApiConfig>(value).unwrap(), config);
+        assert!(playback.is_none());
+    }
+
+    #[test]
+    fn newer_schema_is_kept_and_never_saved() {
+        let newer = json!({
+            "schema_version": CONFIG_SCHEMA_VERSION + 1,
+            "general": { "close_to_tray": true },
+            "lyrics": { "romanize": true }
+        });
+
+        let (value, playback) = migrate_config(newer);
+        assert_eq!(schema_version_of(&value), CONFIG_SCHEMA_VERSION + 1);
+        assert!(playback.is_none());
+
+        let config: ApiConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.schema_version, CONFIG_SCHEMA_VERSION + 1);
+        assert_eq!(config.general.close_to_tray, Some(true));
+        assert!(ensure_writable(&config).is_err());
+
+        let updated = apply_patch(&config, &json!({ "general": { "close_to_tray": false } })).unwrap();
+        assert!(ensure_writable(&updated).is_err(), "patching doesn't make it ours");
+        assert!(ensure_writable(&ApiConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn patch_touches_only_named_fields() {
+        let mut current = ApiConfig::default();
+        current.visualizer.style = Some("wave".to_string());
+        current.cache.max_size_mb = Some(10);
+
+        let updated = apply_patch(&current, &json!({ "general": { "close_to_tray": true } })).unwrap();
+
+        assert_eq!(updated.general.close_to_tray, Some(true));
+        assert_eq!(updated.visualizer, current.visualizer);
+        assert_eq!(updated.cache, current.cache);
+    }
+
+    #[test]
+    fn mistyped_values_only_reset_themselves() {
+        let value = json!({
+            "schema_version": CONFIG_SCHEMA_VERSION,
+            "general": { "close_to_tray": "yes", "enable_notifications": true },
+            "visualizer": 5,
+            "cache": { "max_size_mb": 10 },
+            "ai": { "model": "llama3", "artist_info": { "max_tokens": "lots", "temperature": 0.5 } },
+        });
+        assert!(serde_json::from_value::<ApiConfig>(value.clone()).is_err());
+
+        let (config, errors) = from_value_leniently(&value);
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["ai.artist_info.max_tokens", "general.close_to_tray", "visualizer"]);
+        assert_eq!(config.general.close_to_tray, None);
+        assert_eq!(config.general.enable_notifications, Some(true));
+        assert_eq!(config.visualizer, VisualizerConfig::default());
+        assert_eq!(config.cache.max_size_mb, Some(10));
+        assert_eq!(config.ai.model.as_deref(), Some("llama3"));
+        assert_eq!(config.ai.artist_info.temperature, Some(0.5));
+    }
+
+    #[test]
+    fn patch_null_clears_field() {
+        let mut current = ApiConfig::default();
+        current.cache.max_size_mb = Some(10);
+
+        let updated = apply_patch(&current, &json!({ "cache": { "max_size_mb": null } })).unwrap();
+
+        assert_eq!(updated.cache.max_size_mb, None);
+    }
+
+    #[test]
+    fn patch_merges_nested_groups() {
+        let mut current = ApiConfig::default();
+        current.ai.artist_info.model = Some("gpt-4o".to_string());
+        current.ai.song_context.max_tokens = Some(300);
+
+        let patch = json!({ "ai": { "artist_info": { "max_tokens": 800 }, "song_context": null } });
+        let updated = apply_patch(&current, &patch).unwrap();
+        assert_eq!(updated.ai.artist_info.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(updated.ai.artist_info.max_tokens, Some(800));
+        assert_eq!(updated.ai.song_context, AiFeatureSettings::default());
+
+        let patch = json!({ "ai": { "artist_info": { "temprature": 1.0 }, "lyrics_formatting": { "timeout_secs": "slow" } } });
+        let err = apply_patch(&current, &patch).unwrap_err();
+        let fields: Vec<&str> = err.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, vec!["ai.artist_info.temprature", "ai.lyrics_formatting.timeout_secs"]);
+    }
+
+    #[test]
+    fn patch_rejects_unknown_and_mistyped_settings() {
+        let current = ApiConfig::default();
+
+        let err = apply_patch(&current, &json!({ "general": { "close_to_tary": true } })).unwrap_err();
+        assert_eq!(err.fields[0].field, "general.close_to_tary");
+        assert!(err.message.contains("general.close_to_tary"), "{}", err);
+
+        let err = apply_patch(&current, &json!({ "themes": {} })).unwrap_err();
+        assert_eq!(err.fields[0].field, "themes");
+
+        let err = apply_patch(&current, &json!({ "general": { "close_to_tray": "yes" } })).unwrap_err();
+        assert_eq!(err.fields[0].field, "general.close_to_tray");
+
+        assert!(apply_patch(&current, &json!({ "schema_version": 1 })).is_err());
+        assert!(apply_patch(&current, &json!({ "credentials": { "openai_api_key": "sk" } })).is_err());
+    }
+
+    #[test]
+    fn patch_reports_every_invalid_field_and_clamps_ranges() {
+        let current = ApiConfig::default();
+
+        let patch = json!({ "visualizer": { "style": "lasers", "color": "", "sensitivity": 1000.0 } });
+        let err = apply_patch(&current, &patch).unwrap_err();
+        let fields: Vec<&str> = err.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, vec!["visualizer.style", "visualizer.color"]);
+
+        let patch = json!({ "visualizer": { "sensitivity": 1000.0, "particle_count": -3 } });
+        let updated = apply_patch(&current, &patch).unwrap();
+        assert_eq!(updated.visualizer.sensitivity, Some(2.0));
+        assert_eq!(updated.visualizer.particle_count, Some(20));
     }
 }
@@ -18,10 +18,17 @@ impl Default for DiscordState {
 
 #[tauri::command]
 pub fn update_discord_presence(
+    app: tauri::AppHandle,
     title: String,
     artist: String,
     state: tauri::State<DiscordState>
 ) -> Result<(), String> {
+    // Presence can be turned off per profile; clear anything left from before it was
+    let config = crate::config::load_config(&app);
+    if !config.general.enable_discord_presence.unwrap_or(true) {
+        return clear_discord_presence(state);
+    }
+    
     let mut client_opt = state.client.lock().unwrap();
     
     let details_text = title.to_string();
@@ -38,7 +45,7 @@ pub fn update_discord_presence(
                 Ok(())
             }
             Err(e) => {
-                eprintln!("[Basitune] Failed to update Discord presence: {}", e);
+                log_error!("[Basitune] Failed to update Discord presence: {}", e);
                 
                 // Try to reconnect on connection errors
                 drop(client_opt.take()); // Drop the old client
@@ -57,12 +64,12 @@ pub fn update_discord_presence(
                                     return Ok(());
                                 }
                                 Err(e2) => {
-                                    eprintln!("[Basitune] Failed to set presence after reconnect: {}", e2);
+                                    log_error!("[Basitune] Failed to set presence after reconnect: {}", e2);
                                 }
                             }
                         }
                         Err(e2) => {
-                            eprintln!("[Basitune] Failed to reconnect: {}", e2);
+                            log_error!("[Basitune] Failed to reconnect: {}", e2);
                         }
                     }
                 }
@@ -83,15 +90,15 @@ pub fn update_discord_presence(
                             *client_opt = Some(new_client);
                         }
                         Err(e) => {
-                            eprintln!("[Basitune] Failed to set Discord presence: {}", e);
-                            eprintln!("[Basitune] This usually means the Discord App ID is not registered.");
-                            eprintln!("[Basitune] Register your app at: https://discord.com/developers/applications");
+                            log_error!("[Basitune] Failed to set Discord presence: {}", e);
+                            log_warn!("[Basitune] This usually means the Discord App ID is not registered.");
+                            log_warn!("[Basitune] Register your app at: https://discord.com/developers/applications");
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("[Basitune] Failed to connect to Discord: {}", e);
-                    eprintln!("[Basitune] Make sure Discord is running.");
+                    log_error!("[Basitune] Failed to connect to Discord: {}", e);
+                    log_warn!("[Basitune] Make sure Discord is running.");
                 }
             }
         }
@@ -0,0 +1,326 @@
+// Coalescing of identical in-flight lookups. When the sidebar opens as the track changes,
+// artist info, song context and lyrics can be requested twice for the same key; the second
+// call waits for the first and shares its result instead of paying for another API call and
+// racing it to the cache. Lookups are tagged with the track they belong to, and those for a
+// track that is no longer playing are cancelled when the current track changes.
+
+use crate::ai::streaming::CANCELLED;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Notify};
+
+type Shared = Option<Result<String, String>>;
+
+struct Entry {
+    id: u64,
+    // Normalized artist, or "artist|title", that the lookup is for
+    scope: String,
+    result: watch::Receiver<Shared>,
+    cancel: Arc<Notify>,
+}
+
+// The track being played, as the normalized artist and "artist|title"
+#[derive(Debug, Clone, PartialEq)]
+struct Track {
+    artist: String,
+    song: String,
+}
+
+#[derive(Default)]
+pub struct InFlight {
+    active: Mutex<HashMap<String, Entry>>,
+    current: Mutex<Option<Track>>,
+    next_id: AtomicU64,
+}
+
+enum Role {
+    // Runs the lookup and publishes its result
+    Leader { id: u64, sender: watch::Sender<Shared>, cancel: Arc<Notify> },
+    // Waits for the leader's result
+    Follower(watch::Receiver<Shared>),
+}
+
+// Removes the leader's entry when it finishes or is dropped, so waiting callers aren't stranded
+struct Registration<'a> {
+    registry: &'a InFlight,
+    key: &'a str,
+    id: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut active = self.registry.active.lock().unwrap();
+        if active.get(self.key).is_some_and(|entry| entry.id == self.id) {
+            active.remove(self.key);
+        }
+    }
+}
+
+impl InFlight {
+    // Run `future` for `key` unless the same key is already being looked up, in which case
+    // wait for that lookup and return its result. `scope` is the normalized artist or
+    // "artist|title" the lookup is for.
+    //
+    // A caller whose lookup was cancelled by someone else (e.g. the sidebar replacing a
+    // streaming request) runs it again itself while its track is still current.
+    pub async fn run<F>(&self, key: &str, scope: &str, future: F) -> Result<String, String>
+    where
+        F: Future<Output = Result<String, String>>,
+    {
+        let mut future = Some(future);
+        loop {
+            let role = {
+                let mut active = self.active.lock().unwrap();
+                match active.get(key) {
+                    Some(entry) => Role::Follower(entry.result.clone()),
+                    None => {
+                        let (sender, receiver) = watch::channel(None);
+                        let cancel = Arc::new(Notify::new());
+                        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                        let entry = Entry { id, scope: scope.to_string(), result: receiver, cancel: cancel.clone() };
+                        active.insert(key.to_string(), entry);
+                        Role::Leader { id, sender, cancel }
+                    }
+                }
+            };
+
+            match role {
+                Role::Leader { id, sender, cancel } => {
+                    let _registration = Registration { registry: self, key, id };
+                    let future = future.take().expect("only the leader runs the lookup, and then returns");
+                    let result = tokio::select! {
+                        result = future => result,
+                        _ = cancel.notified() => Err(CANCELLED.to_string()),
+                    };
+                    sender.send_replace(Some(result.clone()));
+                    return result;
+                }
+                Role::Follower(mut receiver) => {
+                    log_debug!("[Basitune] Waiting for the lookup already in flight for {}", key);
+                    let shared = receiver.wait_for(Option::is_some).await.ok().and_then(|result| result.clone());
+                    match shared {
+                        Some(Err(e)) if e == CANCELLED && self.is_current(scope) => continue,
+                        Some(result) => return result,
+                        // The first caller went away without a result; look it up ourselves
+                        None => continue,
+                    }
+                }
+            }
+        }
+    }
+
+    // Record the track now playing and cancel lookups for any other. Lookups for the same
+    // artist (artist info) survive a change of song. Returns how many were cancelled.
+    pub fn set_current(&self, artist: String, song: String) -> usize {
+        let track = Track { artist, song };
+        let mut active = self.active.lock().unwrap();
+        *self.current.lock().unwrap() = Some(track.clone());
+
+        let stale: Vec<String> = active
+            .iter()
+            .filter(|(_, entry)| entry.scope != track.artist && entry.scope != track.song)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &stale {
+            if let Some(entry) = active.remove(key) {
+                // notify_one keeps a permit, so a lookup that hasn't started waiting still stops
+                entry.cancel.notify_one();
+            }
+        }
+        stale.len()
+    }
+
+    // Cancel the lookup running for `key`, if any. Callers waiting on it whose track is
+    // still current look it up again.
+    pub fn cancel(&self, key: &str) -> bool {
+        match self.active.lock().unwrap().remove(key) {
+            Some(entry) => {
+                entry.cancel.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Whether a lookup for `scope` is still wanted; everything is until a track is set
+    fn is_current(&self, scope: &str) -> bool {
+        match &*self.current.lock().unwrap() {
+            Some(track) => scope == track.artist || scope == track.song,
+            None => true,
+        }
+    }
+}
+
+// Run `future` through the app's registry, or directly when there isn't one
+pub async fn coalesce<F>(app_handle: &tauri::AppHandle, key: &str, scope: &str, future: F) -> Result<String, String>
+where
+    F: Future<Output = Result<String, String>>,
+{
+    use tauri::Manager;
+    match app_handle.try_state::<InFlight>() {
+        Some(registry) => registry.run(key, scope, future).await,
+        None => future.await,
+    }
+}
+
+// coalesce for a lookup that streams its reply under `request_id`. A caller that ends up
+// sharing another's result gets it replayed under its own request_id, so its stream ends too.
+pub async fn coalesce_streamed<F>(
+    app_handle: &tauri::AppHandle,
+    key: &str,
+    scope: &str,
+    request_id: Option<&str>,
+    future: F,
+) -> Result<String, String>
+where
+    F: Future<Output = Result<String, String>>,
+{
+    let ran = AtomicBool::new(false);
+    let result = coalesce(app_handle, key, scope, async {
+        ran.store(true, Ordering::Relaxed);
+        future.await
+    })
+    .await;
+
+    if let Some(request_id) = request_id {
+        if !ran.load(Ordering::Relaxed) {
+            crate::ai::streaming::replay(app_handle, request_id, &result);
+        }
+    }
+    result
+}
+
+// Cancel the app's lookup for `key`, if one is running
+pub fn cancel(app_handle: &tauri::AppHandle, key: &str) -> bool {
+    use tauri::Manager;
+    app_handle.try_state::<InFlight>().is_some_and(|registry| registry.cancel(key))
+}
+
+// Called by the sidebar when the playing track changes
+#[tauri::command]
+pub fn set_current_track(title: String, artist: String, app: tauri::AppHandle) -> usize {
+    use crate::utils::normalize_string;
+    use tauri::Manager;
+
+    let artist_key = normalize_string(&artist);
+    let song_key = format!("{}|{}", artist_key, normalize_string(&title));
+    let cancelled = app.state::<InFlight>().set_current(artist_key, song_key);
+    if cancelled > 0 {
+        log_debug!("[Basitune] Cancelled {} lookup(s) for the previous track", cancelled);
+    }
+    cancelled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    async fn slow(calls: &AtomicUsize, value: &str) -> Result<String, String> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok(value.to_string())
+    }
+
+    #[tokio::test]
+    async fn identical_lookups_share_one_call() {
+        let registry = InFlight::default();
+        let calls = AtomicUsize::new(0);
+
+        let (first, second, other) = tokio::join!(
+            registry.run("lyrics:a|b", "a|b", slow(&calls, "first")),
+            registry.run("lyrics:a|b", "a|b", slow(&calls, "second")),
+            registry.run("lyrics:a|c", "a|c", slow(&calls, "other")),
+        );
+        assert_eq!(first.unwrap(), "first");
+        assert_eq!(second.unwrap(), "first");
+        assert_eq!(other.unwrap(), "other");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(registry.active.lock().unwrap().is_empty());
+
+        // Once finished, the key is looked up afresh
+        assert_eq!(registry.run("lyrics:a|b", "a|b", slow(&calls, "again")).await.unwrap(), "again");
+    }
+
+    #[tokio::test]
+    async fn errors_are_shared_too() {
+        let registry = InFlight::default();
+        let failing = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err::<String, _>("Genius API error".to_string())
+        };
+        let (first, second) = tokio::join!(
+            registry.run("lyrics:a|b", "a|b", failing),
+            registry.run("lyrics:a|b", "a|b", async { Ok("unused".to_string()) }),
+        );
+        assert_eq!(first.unwrap_err(), "Genius API error");
+        assert_eq!(second.unwrap_err(), "Genius API error");
+    }
+
+    #[tokio::test]
+    async fn changing_track_cancels_stale_lookups() {
+        let registry = InFlight::default();
+        registry.set_current("a".to_string(), "a|b".to_string());
+        let calls = AtomicUsize::new(0);
+
+        let change_song = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            registry.set_current("a".to_string(), "a|c".to_string())
+        };
+        let (song, waiting, artist, cancelled) = tokio::join!(
+            registry.run("song_context:a|b", "a|b", slow(&calls, "context")),
+            registry.run("song_context:a|b", "a|b", slow(&calls, "context")),
+            registry.run("artist_info:a", "a", slow(&calls, "bio")),
+            change_song,
+        );
+        assert_eq!(cancelled, 1);
+        assert_eq!(song.unwrap_err(), CANCELLED);
+        // The waiting caller is stale as well, so it doesn't try again
+        assert_eq!(waiting.unwrap_err(), CANCELLED);
+        // Same artist, so the bio is still wanted
+        assert_eq!(artist.unwrap(), "bio");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn cancelled_lookups_are_retried_by_waiting_callers() {
+        let registry = InFlight::default();
+        let calls = AtomicUsize::new(0);
+
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            registry.cancel("lyrics:a|b")
+        };
+        let (first, waiting, cancelled) = tokio::join!(
+            registry.run("lyrics:a|b", "a|b", slow(&calls, "stale")),
+            async {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                registry.run("lyrics:a|b", "a|b", slow(&calls, "fresh")).await
+            },
+            cancel,
+        );
+        assert!(cancelled);
+        assert_eq!(first.unwrap_err(), CANCELLED);
+        assert_eq!(waiting.unwrap(), "fresh");
+        assert!(!registry.cancel("lyrics:a|b"));
+    }
+
+    #[tokio::test]
+    async fn waiting_callers_retry_when_the_first_is_dropped() {
+        let registry = InFlight::default();
+        let calls = AtomicUsize::new(0);
+
+        // The first caller gives up before its lookup finishes
+        let abandoned = tokio::time::timeout(Duration::from_millis(10), registry.run("k", "a", slow(&calls, "first")));
+        let (abandoned, waiting) = tokio::join!(abandoned, async {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            registry.run("k", "a", slow(&calls, "second")).await
+        });
+        assert!(abandoned.is_err());
+        assert_eq!(waiting.unwrap(), "second");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
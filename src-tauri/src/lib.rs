@@ -2,16 +2,28 @@
 // This file enables both binary and library builds
 
 // Module declarations
+// logging comes first so its macros are available to the modules below
+#[macro_use]
+pub mod logging;
 pub mod ai;
 pub mod cache;
+pub mod cli;
 pub mod config;
 pub mod discord;
+pub mod http;
+pub mod inflight;
 pub mod notifications;
+pub mod persist;
 pub mod playback;
+pub mod presets;
+pub mod profiles;
+pub mod secrets;
 pub mod sidebar;
 pub mod tray;
 pub mod updater;
 pub mod utils;
+pub mod validation;
+pub mod watcher;
 
 // Re-export commonly used items
 pub use config::{ApiConfig, WindowState};
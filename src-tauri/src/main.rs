@@ -66,6 +66,30 @@ fn save_window_state(app: &tauri::AppHandle) {
 }
 
 fn main() {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::CliCommand::Run(options)) => options,
+        Ok(cli::CliCommand::Version) => {
+            println!("Basitune {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Ok(cli::CliCommand::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("basitune: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    
+    if let Some(level) = options.log_level {
+        logging::set_level(level);
+    }
+    if let Some(dir) = options.data_dir.clone() {
+        profiles::set_data_dir(dir);
+    }
+    let safe_mode = options.safe_mode;
+    
     // Initialize state
     let discord_state = discord::DiscordState::default();
     let playback_state = playback::PlaybackState::default();
@@ -73,29 +97,37 @@ fn main() {
     tauri::Builder::default()
         .manage(discord_state)
         .manage(playback_state)
+        .manage(ai::streaming::AiStreams::default())
+        .manage(inflight::InFlight::default())
+        .manage(http::HttpClient::default())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         // Inject sidebar + volume helpers on every page load so they survive navigations
-        .on_page_load(|window, _payload| {
+        .on_page_load(move |window, _payload| {
+            if safe_mode {
+                log_info!("[Basitune] Safe mode: not injecting scripts");
+                return;
+            }
+            
             let sidebar_script = include_str!("../../src/scripts/sidebar.js");
             let diagnostics_script = include_str!("../../src/scripts/audio-diagnostics.js");
             let playback_script = include_str!("../../src/scripts/playback-controls.js");
             let visualizer_script = include_str!("../../src/scripts/visualizer.js");
 
             if let Err(e) = window.eval(sidebar_script) {
-                eprintln!("[Basitune] Failed to inject sidebar: {}", e);
+                log_error!("[Basitune] Failed to inject sidebar: {}", e);
             }
 
             if let Err(e) = window.eval(diagnostics_script) {
-                eprintln!("[Basitune] Failed to inject audio diagnostics: {}", e);
+                log_error!("[Basitune] Failed to inject audio diagnostics: {}", e);
             }
 
             if let Err(e) = window.eval(playback_script) {
-                eprintln!("[Basitune] Failed to inject playback controls: {}", e);
+                log_error!("[Basitune] Failed to inject playback controls: {}", e);
             }
 
             if let Err(e) = window.eval(visualizer_script) {
-                eprintln!("[Basitune] Failed to inject visualizer: {}", e);
+                log_error!("[Basitune] Failed to inject visualizer: {}", e);
             }
 
             // Attempt to restore playback position
@@ -116,7 +148,7 @@ fn main() {
                         
                         // Check if auto-play is enabled in settings
                         let config = config::load_config(&app_handle);
-                        let should_auto_play = saved.was_playing && config.resume_playback_on_startup.unwrap_or(true);
+                        let should_auto_play = saved.was_playing && config.general.resume_playback_on_startup.unwrap_or(true);
                         
                         let restore_script = format!(
                             r#"
@@ -144,13 +176,24 @@ fn main() {
                         );
                         
                         if let Err(e) = window_clone.eval(&restore_script) {
-                            eprintln!("[Basitune] Failed to restore playback position: {}", e);
+                            log_error!("[Basitune] Failed to restore playback position: {}", e);
                         }
                     }
                 });
             }
         })
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // Launching with a different --profile switches the running instance over
+            if let Ok(cli::CliCommand::Run(cli::CliOptions { profile: Some(name), .. })) = cli::parse(args.iter().skip(1)) {
+                if name != profiles::active() {
+                    save_window_state(app);
+                    if let Err(e) = profiles::switch(app, &name) {
+                        log_warn!("[Basitune] {}", e);
+                    }
+                    return;
+                }
+            }
+            
             // When a second instance is attempted, focus the existing window
             let windows = app.webview_windows();
             if let Some(window) = windows.values().next() {
@@ -160,9 +203,20 @@ fn main() {
         }))
         .invoke_handler(tauri::generate_handler![
             ai::openai::get_artist_info, 
+            ai::profile::get_artist_profile,
             ai::openai::get_song_context, 
+            ai::streaming::cancel_ai_request,
+            inflight::set_current_track,
+            ai::usage::get_ai_usage,
+            ai::chat::chat_about_song,
+            ai::chat::get_song_chat,
+            ai::chat::clear_song_chat,
+            ai::chat::export_song_chat,
             ai::genius::get_lyrics,
             ai::genius::search_lyrics,
+            ai::genius::clear_lyrics_misses,
+            ai::genius::pin_lyrics_source,
+            ai::genius::unpin_lyrics_source,
             sidebar::get_sidebar_visible,
             sidebar::set_sidebar_visible,
             sidebar::get_sidebar_width,
@@ -176,7 +230,16 @@ fn main() {
             discord::clear_discord_presence,
             config::get_config,
             config::save_config,
+            config::update_config,
+            secrets::set_api_secret,
             config::save_visualizer_settings,
+            presets::list_visualizer_presets,
+            presets::save_visualizer_preset,
+            presets::rename_visualizer_preset,
+            presets::delete_visualizer_preset,
+            presets::apply_visualizer_preset,
+            presets::export_visualizer_presets,
+            presets::import_visualizer_presets,
             updater::get_app_metadata,
             updater::get_changelog,
             updater::check_for_updates,
@@ -193,12 +256,44 @@ fn main() {
             notifications::show_notification,
             config::save_playback_position,
             config::get_playback_position,
-            playback::audio_context_ready
+            playback::audio_context_ready,
+            cache::commands::list_cache_entries,
+            cache::commands::get_cache_entry,
+            cache::commands::delete_cache_entries,
+            cache::commands::purge_cache_category,
+            cache::commands::invalidate_cache_entries,
+            cache::commands::get_cache_stats,
+            persist::get_recovery_notices,
+            profiles::list_profiles,
+            profiles::switch_profile
         ])
-        .setup(|app| {
+        .setup(move |app| {
             use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
             use tauri::tray::{TrayIconBuilder, TrayIconEvent};
             
+            // Pick the profile before anything reads its files
+            let profile = profiles::init(app.handle(), options.profile.clone());
+            
+            // The main window is created here instead of from tauri.conf.json so it can use
+            // the profile's own webview data directory (and with it, its own login)
+            let window_config = app.config().app.windows.iter()
+                .find(|w| w.label == "main")
+                .cloned()
+                .ok_or("Main window missing from tauri.conf.json")?;
+            let mut window_builder = tauri::WebviewWindowBuilder::from_config(app.handle(), &window_config)?;
+            if let Some(dir) = profiles::webview_data_dir(app.handle()) {
+                window_builder = window_builder
+                    .data_directory(dir)
+                    .title(format!("{} ({})", window_config.title, profile));
+            }
+            window_builder.build()?;
+            
+            // Load the content cache into memory; changes are flushed to disk in the background
+            app.manage(cache::ContentCache::load(app.handle()));
+            cache::ContentCache::start_flush_task(app.handle().clone());
+            app.manage(ai::usage::UsageLedger::load(profiles::data_dir(app.handle()).join("ai-usage.json")));
+            app.manage(ai::chat::ChatStore::load(profiles::data_dir(app.handle()).join("song-chats.json")));
+            
             // Build initial tray menu (nothing playing at startup)
             let show_hide = MenuItem::with_id(app, "show_hide", "Show/Hide", true, None::<&str>)?;
             let previous_track = MenuItem::with_id(app, "previous_track", "Previous Track", true, None::<&str>)?;
@@ -207,6 +302,7 @@ fn main() {
             
             let separator1 = PredefinedMenuItem::separator(app)?;
             let separator2 = PredefinedMenuItem::separator(app)?;
+            let profile_menu = tray::profile_submenu(app.handle())?;
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             
             let menu = Menu::with_items(app, &[
@@ -216,6 +312,7 @@ fn main() {
                 &play,
                 &next_track,
                 &separator2,
+                &profile_menu,
                 &quit
             ])?;
             
@@ -321,6 +418,19 @@ fn main() {
                             }
                             app.exit(0);
                         }
+                        id if id.starts_with(tray::PROFILE_MENU_PREFIX) => {
+                            let name = &id[tray::PROFILE_MENU_PREFIX.len()..];
+                            if name == profiles::active() {
+                                // Clicking toggled the check mark off; rebuild to restore it
+                                let playback_state: tauri::State<playback::PlaybackState> = app.state();
+                                let _ = tray::rebuild_tray_menu(app, &playback_state.get_state());
+                            } else {
+                                save_window_state(app);
+                                if let Err(e) = profiles::switch(app, name) {
+                                    log_warn!("[Basitune] {}", e);
+                                }
+                            }
+                        }
                         _ => {}
                     }
                 })
@@ -340,10 +450,16 @@ fn main() {
                 .build(app)?;
             
             // Initialize window state manager
+            if options.reset_window_state {
+                sidebar::WindowStateManager::reset_saved_state(app.handle());
+            }
             let state_manager = sidebar::WindowStateManager::new(app.handle().clone());
             let state = state_manager.get();
             app.manage(state_manager);
             
+            // Reload settings files edited outside the app
+            watcher::start(app.handle().clone());
+            
             // Check for updates on startup (skip in debug/dev to avoid noisy failures)
             let is_dev = cfg!(debug_assertions);
             if !is_dev {
@@ -358,7 +474,7 @@ fn main() {
                         Ok(updater) => {
                             match updater.check().await {
                                 Ok(Some(update)) => {
-                                    println!("[Basitune] Update available: {} -> {}", 
+                                    log_info!("[Basitune] Update available: {} -> {}", 
                                         update.current_version, update.version);
                                     
                                     if let Some(window) = app_handle.get_webview_window("main") {
@@ -379,13 +495,13 @@ fn main() {
                                             }
                                         },
                                         || {
-                                            println!("[Basitune] Update installed, restart to apply");
+                                            log_info!("[Basitune] Update installed, restart to apply");
                                         }
                                     ).await;
                                     
                                     match download_result {
                                         Ok(_) => {
-                                            println!("[Basitune] Update downloaded and ready to install");
+                                            log_info!("[Basitune] Update downloaded and ready to install");
                                             if let Some(w) = app_handle.get_webview_window("main") {
                                                 let _ = w.eval(
                                                     r#"window.showUpdateNotification('Update ready! Restart Basitune to apply.', true)"#
@@ -393,7 +509,7 @@ fn main() {
                                             }
                                         }
                                         Err(e) => {
-                                            eprintln!("[Basitune] Failed to install update: {}", e);
+                                            log_error!("[Basitune] Failed to install update: {}", e);
                                             if let Some(w) = app_handle.get_webview_window("main") {
                                                 let _ = w.eval(
                                                     r#"window.showUpdateNotification('Update failed. Please try again later.', true)"#
@@ -403,40 +519,40 @@ fn main() {
                                     }
                                 }
                                 Ok(None) => {
-                                    println!("[Basitune] No updates available");
+                                    log_info!("[Basitune] No updates available");
                                 }
                                 Err(e) => {
-                                    eprintln!("[Basitune] Failed to check for updates: {}", e);
+                                    log_error!("[Basitune] Failed to check for updates: {}", e);
                                 }
                             }
                         }
-                        Err(e) => eprintln!("[Basitune] Failed to get updater: {}", e),
+                        Err(e) => log_error!("[Basitune] Failed to get updater: {}", e),
                     }
                 });
             } else {
-                println!("[Basitune] Skipping updater in debug/dev builds");
+                log_info!("[Basitune] Skipping updater in debug/dev builds");
             }
             
             // Get the main window
             let main_window = app.get_webview_window("main").expect("Failed to get main window");
             
             // Apply saved window state
-            println!("[Basitune] Applying window state: {}x{} at ({}, {}), maximized={}", 
+            log_debug!("[Basitune] Applying window state: {}x{} at ({}, {}), maximized={}", 
                      state.width, state.height, state.x, state.y, state.maximized);
             
             // Validate position is within screen bounds (important after display config changes)
             // Skip validation for maximized windows since they'll maximize to current monitor anyway
             let position_valid = if state.maximized {
-                println!("[Basitune] Window is maximized, skipping position validation");
+                log_debug!("[Basitune] Window is maximized, skipping position validation");
                 true
             } else if let Ok(monitors) = main_window.available_monitors() {
-                println!("[Basitune] Checking {} available monitor(s)", monitors.len());
+                log_debug!("[Basitune] Checking {} available monitor(s)", monitors.len());
                 
                 let is_valid = monitors.iter().any(|monitor| {
                     let mon_pos = monitor.position();
                     let mon_size = monitor.size();
                     
-                    println!("[Basitune]   Monitor: {}x{} at ({}, {})", 
+                    log_debug!("[Basitune]   Monitor: {}x{} at ({}, {})", 
                              mon_size.width, mon_size.height, mon_pos.x, mon_pos.y);
                     
                     // Check if saved position is within this monitor's bounds
@@ -448,7 +564,7 @@ fn main() {
                                 state.y < mon_pos.y + (mon_size.height as i32);
                     
                     if valid {
-                        println!("[Basitune]     ✓ Position is valid on this monitor");
+                        log_debug!("[Basitune]     ✓ Position is valid on this monitor");
                     }
                     
                     valid
@@ -456,31 +572,31 @@ fn main() {
                 
                 is_valid
             } else {
-                eprintln!("[Basitune] Failed to get monitor information");
+                log_warn!("[Basitune] Failed to get monitor information");
                 false
             };
             
             let is_off_screen = !position_valid;
             
             if is_off_screen {
-                println!("[Basitune] WARNING: Saved position ({}, {}) is off-screen, will use default position", state.x, state.y);
+                log_warn!("[Basitune] Saved position ({}, {}) is off-screen, will use default position", state.x, state.y);
             } else {
-                println!("[Basitune] Position validation passed");
+                log_debug!("[Basitune] Position validation passed");
             }
             
             if state.maximized {
                 // For maximized windows: use monitor index if available (Wayland-friendly)
-                println!("[Basitune] === RESTORE: Maximized window ===");
-                println!("[Basitune]   Saved monitor index: {:?}", state.monitor_index);
-                println!("[Basitune]   Saved position: ({}, {})", state.x, state.y);
+                log_debug!("[Basitune] === RESTORE: Maximized window ===");
+                log_debug!("[Basitune]   Saved monitor index: {:?}", state.monitor_index);
+                log_debug!("[Basitune]   Saved position: ({}, {})", state.x, state.y);
                 
                 // Log ALL monitors at restore time
                 if let Ok(all_monitors) = main_window.available_monitors() {
-                    println!("[Basitune] === RESTORE: Available monitors ({}) ===", all_monitors.len());
+                    log_debug!("[Basitune] === RESTORE: Available monitors ({}) ===", all_monitors.len());
                     for (idx, monitor) in all_monitors.iter().enumerate() {
                         let pos = monitor.position();
                         let size = monitor.size();
-                        println!("[Basitune]   Monitor {}: {}x{} at ({}, {}) - Name: {:?}", 
+                        log_debug!("[Basitune]   Monitor {}: {}x{} at ({}, {}) - Name: {:?}", 
                                  idx, size.width, size.height, pos.x, pos.y, monitor.name());
                     }
                 }
@@ -494,28 +610,30 @@ fn main() {
                         if let Some(target_monitor) = monitors.into_iter().nth(monitor_idx) {
                             let mon_size = target_monitor.size();
                             let mon_name = target_monitor.name();
-                            println!("[Basitune] === RESTORE: Targeting monitor {} ===", monitor_idx);
-                            println!("[Basitune]   Size: {}x{}", mon_size.width, mon_size.height);
-                            println!("[Basitune]   Name: {:?}", mon_name);
+                            log_debug!("[Basitune] === RESTORE: Targeting monitor {} ===", monitor_idx);
+                            log_debug!("[Basitune]   Size: {}x{}", mon_size.width, mon_size.height);
+                            log_debug!("[Basitune]   Name: {:?}", mon_name);
                             
                             // Critical: Set window to EXACT monitor dimensions before showing
                             // This gives Wayland compositor a strong hint about intended monitor
                             let _ = main_window.set_size(PhysicalSize::new(mon_size.width, mon_size.height));
                         } else {
-                            println!("[Basitune] Monitor index {} not found", monitor_idx);
+                            log_debug!("[Basitune] Monitor index {} not found", monitor_idx);
                             let _ = main_window.set_size(PhysicalSize::new(state.width, state.height));
                         }
                     }
                 } else {
-                    println!("[Basitune] No monitor index saved");
+                    log_debug!("[Basitune] No monitor index saved");
                     let _ = main_window.set_size(PhysicalSize::new(state.width, state.height));
                 }
                 
-                println!("[Basitune] Showing window...");
-                let _ = main_window.show();
-                std::thread::sleep(Duration::from_millis(300));
+                if !options.start_hidden {
+                    log_debug!("[Basitune] Showing window...");
+                    let _ = main_window.show();
+                    std::thread::sleep(Duration::from_millis(300));
+                }
                 
-                println!("[Basitune] Maximizing...");
+                log_debug!("[Basitune] Maximizing...");
                 let _ = main_window.maximize();
                 
                 // Verify final position
@@ -523,9 +641,9 @@ fn main() {
                 if let Ok(Some(monitor)) = main_window.current_monitor() {
                     let mon_pos = monitor.position();
                     let mon_name = monitor.name();
-                    println!("[Basitune] === RESTORE: Final result ===");
-                    println!("[Basitune]   Window on monitor at ({}, {})", mon_pos.x, mon_pos.y);
-                    println!("[Basitune]   Monitor name: {:?}", mon_name);
+                    log_debug!("[Basitune] === RESTORE: Final result ===");
+                    log_debug!("[Basitune]   Window on monitor at ({}, {})", mon_pos.x, mon_pos.y);
+                    log_debug!("[Basitune]   Monitor name: {:?}", mon_name);
                 }
             } else {
                 let _ = main_window.set_size(PhysicalSize::new(state.width, state.height));
@@ -534,9 +652,17 @@ fn main() {
                 }
                 
                 // Show the window
-                let _ = main_window.show();
+                if !options.start_hidden {
+                    let _ = main_window.show();
+                }
+            }
+            if options.start_hidden {
+                log_info!("[Basitune] Started hidden; open the window from the tray");
             }
 
+            // Let the user know if any files had to be recovered while loading
+            notifications::show_recovery_notices(&persist::recovery_notices());
+
             // Handle window close event - either minimize to tray or save window state
             let app_handle = app.handle().clone();
             let window_clone = main_window.clone();
@@ -544,11 +670,11 @@ fn main() {
                 if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                     // Check if close-to-tray is enabled
                     let config = config::load_config(&app_handle);
-                    let close_to_tray = config.close_to_tray.unwrap_or(false);
+                    let close_to_tray = config.general.close_to_tray.unwrap_or(false);
                     
                     if close_to_tray {
                         // Hide window instead of closing
-                        println!("[Basitune] Closing to tray");
+                        log_info!("[Basitune] Closing to tray");
                         let _ = window_clone.hide();
                         api.prevent_close();
                     } else {
@@ -560,6 +686,14 @@ fn main() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Write any batched cache changes before the process ends
+                if let Some(cache) = app_handle.try_state::<cache::ContentCache>() {
+                    cache.flush();
+                }
+            }
+        });
 }
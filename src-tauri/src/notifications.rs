@@ -12,7 +12,7 @@ pub fn show_notification(
 ) -> Result<(), String> {
     // Check if notifications are enabled
     let config = load_config(&app);
-    let enabled = config.enable_notifications.unwrap_or(false);
+    let enabled = config.general.enable_notifications.unwrap_or(false);
     
     if !enabled {
         return Ok(());
@@ -95,7 +95,7 @@ pub fn show_notification(
                     });
                 },
                 Err(e) => {
-                    eprintln!("[Basitune] Failed to show notification: {}", e);
+                    log_error!("[Basitune] Failed to show notification: {}", e);
                 }
             }
         });
@@ -112,10 +112,28 @@ pub fn show_notification(
             .show()
             .map_err(|e| {
                 let err_msg = format!("Failed to show notification: {}", e);
-                eprintln!("[Basitune] {}", err_msg);
+                log_error!("[Basitune] {}", err_msg);
                 err_msg
             })?;
     }
     
     Ok(())
 }
+
+// Tell the user that a settings or cache file had to be recovered at startup.
+// Shown regardless of the notification setting, since it may mean lost settings.
+pub fn show_recovery_notices(notices: &[String]) {
+    if notices.is_empty() {
+        return;
+    }
+    
+    let body = notices.join("\n");
+    if let Err(e) = Notification::new()
+        .summary("Basitune recovered damaged files")
+        .body(&body)
+        .appname("Basitune")
+        .show()
+    {
+        log_error!("[Basitune] Failed to show recovery notification: {}", e);
+    }
+}
@@ -0,0 +1,304 @@
+// Crash-safe persistence for the JSON files in the app data directory.
+//
+// Writes go to a uniquely named temp file that is fsynced and renamed over the original,
+// so concurrent writers never rename each other's partial files into place and a crash
+// leaves either the old file or the new one, never a truncated mix. The previous good
+// copy is kept as `<file>.bak` and used to recover when the main file fails to parse.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Numbers temp files, so each write in this process gets its own
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Recoveries that happened this session, waiting to be shown to the user
+static RECOVERY_NOTICES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+pub fn record_recovery(message: String) {
+    log_warn!("[Basitune] {}", message);
+    RECOVERY_NOTICES.lock().unwrap().push(message);
+}
+
+pub fn recovery_notices() -> Vec<String> {
+    RECOVERY_NOTICES.lock().unwrap().clone()
+}
+
+// Write bytes to `path` atomically: temp file, fsync, rename, fsync the directory
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_as(path, contents, false)
+}
+
+// Like write_atomic, for files only the current user may read (e.g. an encryption key).
+// The temp file is created with mode 0600, so the contents are never readable by others.
+pub fn write_atomic_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_as(path, contents, true)
+}
+
+// `<file>.<pid>.<n>.tmp`, unique to this write even with other processes writing too
+fn temp_path(path: &Path) -> PathBuf {
+    let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    sibling(path, &format!(".{}.{}.tmp", std::process::id(), n))
+}
+
+fn create_temp(path: &Path, private: bool) -> std::io::Result<File> {
+    // create_new, so a leftover file never lends the write its permissions
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if private {
+            options.mode(0o600);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(path)
+}
+
+fn write_atomic_as(path: &Path, contents: &[u8], private: bool) -> Result<(), String> {
+    let label = file_label(path);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory for {}: {}", label, e))?;
+    }
+
+    let tmp_path = temp_path(path);
+    let written = create_temp(&tmp_path, private).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Failed to write {}: {}", label, e));
+    }
+
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace {}: {}", label, e)
+    })?;
+
+    // Make the rename itself durable (not supported on Windows, where rename is already durable enough)
+    #[cfg(unix)]
+    {
+        if let Some(parent) = path.parent() {
+            if let Ok(dir) = File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Serialize `value` as pretty JSON and write it atomically. The file being replaced is
+// kept as `.bak`, but only if it still parses, so a damaged file never overwrites the
+// last good backup.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", file_label(path), e))?;
+
+    if let Ok(existing) = fs::read(path) {
+        if serde_json::from_slice::<serde_json::Value>(&existing).is_ok() {
+            if let Err(e) = write_atomic(&backup_path(path), &existing) {
+                log_error!("[Basitune] Failed to back up {}: {}", file_label(path), e);
+            }
+        }
+    }
+
+    write_atomic(path, json.as_bytes())
+}
+
+// Read and parse a JSON file, falling back to its `.bak` copy if the main file is
+// damaged. Returns None if the file is missing or neither copy is usable.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let label = file_label(path);
+
+    // A missing file is normal (first launch, or deleted to reset); only damage triggers recovery
+    let contents = fs::read_to_string(path).ok()?;
+    let error = match serde_json::from_str::<T>(&contents) {
+        Ok(value) => return Some(value),
+        Err(e) => e,
+    };
+
+    // Keep the damaged file for manual recovery instead of letting the next save overwrite it
+    let kept = quarantine(path);
+
+    let restored = fs::read_to_string(backup_path(path))
+        .ok()
+        .and_then(|backup| serde_json::from_str::<T>(&backup).ok().map(|value| (backup, value)));
+
+    match restored {
+        Some((backup, value)) => {
+            if let Err(e) = write_atomic(path, backup.as_bytes()) {
+                log_error!("[Basitune] Failed to restore {}: {}", label, e);
+            }
+            record_recovery(format!(
+                "{} was damaged ({}) and has been restored from its last good backup",
+                label, error
+            ));
+            Some(value)
+        }
+        None => {
+            record_recovery(format!(
+                "{} was damaged ({}) and no backup was available, so defaults are in use{}",
+                label,
+                error,
+                kept.map(|p| format!(". The damaged file was kept as {}", p.display())).unwrap_or_default()
+            ));
+            None
+        }
+    }
+}
+
+// Read and parse a JSON file without any recovery, for re-reading files the user may be
+// in the middle of editing. Ok(None) if the file is missing.
+pub fn read_json_strict<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    let label = file_label(path);
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", label, e)),
+    };
+
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("{} is not valid: {}", label, e))
+}
+
+// Move a damaged file aside as `<file>.corrupt`. Returns the new path if it was moved.
+pub fn quarantine(path: &Path) -> Option<PathBuf> {
+    let target = sibling(path, ".corrupt");
+    fs::rename(path, &target).ok().map(|_| target)
+}
+
+// Recoveries that happened this session, so the settings UI can explain them
+#[tauri::command]
+pub fn get_recovery_notices() -> Vec<String> {
+    recovery_notices()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("basitune-persist-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn write_then_read_roundtrip() {
+        let path = temp_dir("roundtrip").join("config.json");
+        let value: HashMap<String, u32> = [("a".to_string(), 1)].into_iter().collect();
+
+        write_json(&path, &value).unwrap();
+
+        assert_eq!(read_json::<HashMap<String, u32>>(&path), Some(value));
+        let leftovers: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "temp file should be renamed away");
+    }
+
+    #[test]
+    fn second_write_keeps_previous_copy_as_backup() {
+        let path = temp_dir("backup").join("config.json");
+
+        write_json(&path, &1u32).unwrap();
+        write_json(&path, &2u32).unwrap();
+
+        let backup: u32 = serde_json::from_str(&fs::read_to_string(backup_path(&path)).unwrap()).unwrap();
+        assert_eq!(backup, 1);
+    }
+
+    #[test]
+    fn truncated_file_recovers_from_backup() {
+        let path = temp_dir("recover").join("config.json");
+        write_json(&path, &"first".to_string()).unwrap();
+        write_json(&path, &"second".to_string()).unwrap();
+
+        // Simulate a torn write
+        fs::write(&path, "\"sec").unwrap();
+
+        assert_eq!(read_json::<String>(&path), Some("first".to_string()));
+        assert!(sibling(&path, ".corrupt").exists(), "damaged file should be kept");
+        assert_eq!(read_json::<String>(&path), Some("first".to_string()), "main file should be restored");
+    }
+
+    #[test]
+    fn damaged_file_is_not_used_as_backup() {
+        let path = temp_dir("no-bad-backup").join("config.json");
+        write_json(&path, &1u32).unwrap();
+        write_json(&path, &2u32).unwrap();
+        fs::write(&path, "{").unwrap();
+
+        write_json(&path, &3u32).unwrap();
+
+        let backup: u32 = serde_json::from_str(&fs::read_to_string(backup_path(&path)).unwrap()).unwrap();
+        assert_eq!(backup, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_only_readable_by_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = temp_dir("private").join("secrets.key");
+        write_atomic_private(&path, b"key").unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read(&path).unwrap(), b"key");
+    }
+
+    #[test]
+    fn concurrent_writes_never_mix() {
+        let path = temp_dir("concurrent").join("ai-usage.json");
+        let contents: Vec<String> = (0..8).map(|i| i.to_string().repeat(64 * 1024)).collect();
+
+        std::thread::scope(|scope| {
+            for text in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        write_atomic(path, text.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&written), "file should hold one complete write");
+    }
+
+    #[test]
+    fn missing_file_reads_as_none() {
+        let path = temp_dir("missing").join("config.json");
+        assert_eq!(read_json::<u32>(&path), None);
+    }
+}
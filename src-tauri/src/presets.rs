@@ -0,0 +1,432 @@
+// Named visualizer presets. Built-in presets ship with the app and can't be changed; user
+// presets are stored per profile in visualizer-presets.json. Presets can be exported to and
+// imported from a JSON file for sharing.
+
+use crate::config::VisualizerSettings;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+const PRESETS_FILE_VERSION: u32 = 1;
+const EXPORT_FORMAT: &str = "basitune-visualizer-presets";
+const MAX_NAME_LEN: usize = 48;
+const EXPORT_FILE_STEM: &str = "basitune-visualizer-presets";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisualizerPreset {
+    pub name: String,
+    pub settings: VisualizerSettings,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PresetsFile {
+    version: u32,
+    presets: Vec<VisualizerPreset>,
+}
+
+// Layout of exported files
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetExport {
+    format: String,
+    version: u32,
+    presets: Vec<VisualizerPreset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetInfo {
+    pub name: String,
+    pub builtin: bool,
+    pub settings: VisualizerSettings,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn preset(
+    name: &str,
+    style: &str,
+    color: &str,
+    color_palette: &str,
+    sensitivity: f64,
+    animation_speed: f64,
+    glow_intensity: Option<f64>,
+    line_thickness: f64,
+) -> VisualizerPreset {
+    VisualizerPreset {
+        name: name.to_string(),
+        settings: VisualizerSettings {
+            style: style.to_string(),
+            color: color.to_string(),
+            sensitivity,
+            color_palette: color_palette.to_string(),
+            animation_speed,
+            glow_enabled: glow_intensity.is_some(),
+            glow_intensity: glow_intensity.unwrap_or(10.0),
+            bar_spacing: 1.0,
+            particle_count: 80,
+            line_thickness,
+        },
+    }
+}
+
+// Shipped with the app; "Classic" matches the visualizer's defaults
+pub fn builtin_presets() -> Vec<VisualizerPreset> {
+    vec![
+        preset("Classic", "bars", "#ff0000", "single", 1.0, 1.0, None, 2.0),
+        preset("Neon Pulse", "circular", "#00ffcc", "neon", 1.3, 1.0, Some(14.0), 3.0),
+        preset("Ocean Drift", "wave", "#0077ff", "ocean", 0.9, 0.7, Some(6.0), 2.5),
+        preset("Campfire", "particles", "#ff6600", "fire", 1.2, 1.2, Some(8.0), 2.0),
+        preset("Synthwave", "spiral", "#ff00ff", "synthwave", 1.1, 1.5, Some(12.0), 2.0),
+    ]
+}
+
+// Preset names are shown in the UI and stored as-is, so only trim and bound them.
+// Names are unique ignoring case.
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Preset names must be 1 to {} characters long", MAX_NAME_LEN));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Preset names can't contain control characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn is_builtin(name: &str) -> bool {
+    builtin_presets().iter().any(|p| same_name(&p.name, name))
+}
+
+fn get_presets_path(app_handle: &tauri::AppHandle) -> PathBuf {
+    crate::profiles::data_dir(app_handle).join("visualizer-presets.json")
+}
+
+fn load_user_presets(app_handle: &tauri::AppHandle) -> Vec<VisualizerPreset> {
+    crate::persist::read_json::<PresetsFile>(&get_presets_path(app_handle))
+        .map(|file| file.presets)
+        .unwrap_or_default()
+}
+
+fn save_user_presets(app_handle: &tauri::AppHandle, presets: Vec<VisualizerPreset>) -> Result<(), String> {
+    let file = PresetsFile { version: PRESETS_FILE_VERSION, presets };
+    crate::persist::write_json(&get_presets_path(app_handle), &file)
+}
+
+fn find<'a>(presets: &'a [VisualizerPreset], name: &str) -> Option<&'a VisualizerPreset> {
+    presets.iter().find(|p| same_name(&p.name, name))
+}
+
+// Built-in presets first, then the user's in the order they were saved
+fn all_presets(user: Vec<VisualizerPreset>) -> Vec<PresetInfo> {
+    let builtin = builtin_presets().into_iter().map(|p| (p, true));
+    let user = user.into_iter().map(|p| (p, false));
+    builtin
+        .chain(user)
+        .map(|(p, builtin)| PresetInfo { name: p.name, builtin, settings: p.settings })
+        .collect()
+}
+
+// Add or replace a user preset. Settings are validated like save_visualizer_settings.
+fn upsert(presets: &mut Vec<VisualizerPreset>, name: &str, settings: VisualizerSettings) -> Result<String, String> {
+    let name = validate_name(name)?;
+    if is_builtin(&name) {
+        return Err(format!("'{}' is a built-in preset; save under another name", name));
+    }
+    let settings = crate::validation::visualizer_settings(settings)?;
+
+    match presets.iter_mut().find(|p| same_name(&p.name, &name)) {
+        Some(existing) => {
+            existing.name = name.clone();
+            existing.settings = settings;
+        }
+        None => presets.push(VisualizerPreset { name: name.clone(), settings }),
+    }
+    Ok(name)
+}
+
+fn rename(presets: &mut [VisualizerPreset], old_name: &str, new_name: &str) -> Result<String, String> {
+    let new_name = validate_name(new_name)?;
+    if is_builtin(old_name) {
+        return Err(format!("Built-in preset '{}' can't be renamed", old_name.trim()));
+    }
+    if is_builtin(&new_name) {
+        return Err(format!("'{}' is the name of a built-in preset", new_name));
+    }
+
+    let conflict = presets
+        .iter()
+        .any(|p| same_name(&p.name, &new_name) && !same_name(&p.name, old_name));
+    if conflict {
+        return Err(format!("A preset named '{}' already exists", new_name));
+    }
+
+    let preset = presets
+        .iter_mut()
+        .find(|p| same_name(&p.name, old_name))
+        .ok_or_else(|| format!("No preset named '{}'", old_name.trim()))?;
+    preset.name = new_name.clone();
+    Ok(new_name)
+}
+
+fn remove(presets: &mut Vec<VisualizerPreset>, name: &str) -> Result<(), String> {
+    if is_builtin(name) {
+        return Err(format!("Built-in preset '{}' can't be deleted", name.trim()));
+    }
+    let before = presets.len();
+    presets.retain(|p| !same_name(&p.name, name));
+    if presets.len() == before {
+        return Err(format!("No preset named '{}'", name.trim()));
+    }
+    Ok(())
+}
+
+fn export_json(presets: Vec<VisualizerPreset>) -> Result<String, String> {
+    let export = PresetExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: PRESETS_FILE_VERSION,
+        presets,
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| format!("Failed to serialize presets: {}", e))
+}
+
+fn parse_export(json: &str) -> Result<Vec<VisualizerPreset>, String> {
+    let export: PresetExport =
+        serde_json::from_str(json).map_err(|e| format!("Not a Basitune preset file: {}", e))?;
+    if export.format != EXPORT_FORMAT {
+        return Err("Not a Basitune preset file".to_string());
+    }
+    if export.version > PRESETS_FILE_VERSION {
+        return Err(format!(
+            "Preset file version {} is newer than this version of Basitune supports",
+            export.version
+        ));
+    }
+    Ok(export.presets)
+}
+
+// First free name of the form "Name", "Name (2)", "Name (3)", ... The name is shortened
+// as needed so the suffix still fits within MAX_NAME_LEN.
+fn unique_name(presets: &[VisualizerPreset], name: &str) -> String {
+    let taken = |candidate: &str| is_builtin(candidate) || find(presets, candidate).is_some();
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!(" ({})", n);
+            let base: String = name.chars().take(MAX_NAME_LEN.saturating_sub(suffix.chars().count())).collect();
+            format!("{}{}", base.trim_end(), suffix)
+        })
+        .find(|candidate| !taken(candidate))
+        .expect("ran out of preset names")
+}
+
+// First unused export file name in `dir`: "basitune-visualizer-presets.json", then
+// "basitune-visualizer-presets-2.json" and so on, so earlier exports are kept
+fn export_path(dir: &Path) -> PathBuf {
+    (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("{}.json", EXPORT_FILE_STEM)),
+            n => dir.join(format!("{}-{}.json", EXPORT_FILE_STEM, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("ran out of export file names")
+}
+
+// Add imported presets. With `overwrite`, a user preset of the same name is replaced;
+// otherwise the import is renamed. Imports never replace built-in presets.
+fn merge(presets: &mut Vec<VisualizerPreset>, imported: Vec<VisualizerPreset>, overwrite: bool) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for preset in imported {
+        let name = validate_name(&preset.name)?;
+        let name = if overwrite && !is_builtin(&name) {
+            name
+        } else {
+            unique_name(presets, &name)
+        };
+        names.push(upsert(presets, &name, preset.settings)?);
+    }
+    Ok(names)
+}
+
+#[tauri::command]
+pub fn list_visualizer_presets(app: tauri::AppHandle) -> Vec<PresetInfo> {
+    all_presets(load_user_presets(&app))
+}
+
+// Save settings under a name, replacing a user preset with that name
+#[tauri::command]
+pub fn save_visualizer_preset(app: tauri::AppHandle, name: String, settings: VisualizerSettings) -> Result<String, String> {
+    let mut presets = load_user_presets(&app);
+    let name = upsert(&mut presets, &name, settings)?;
+    save_user_presets(&app, presets)?;
+    log_info!("[Basitune] Saved visualizer preset '{}'", name);
+    Ok(name)
+}
+
+#[tauri::command]
+pub fn rename_visualizer_preset(app: tauri::AppHandle, old_name: String, new_name: String) -> Result<String, String> {
+    let mut presets = load_user_presets(&app);
+    let name = rename(&mut presets, &old_name, &new_name)?;
+    save_user_presets(&app, presets)?;
+    Ok(name)
+}
+
+#[tauri::command]
+pub fn delete_visualizer_preset(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let mut presets = load_user_presets(&app);
+    remove(&mut presets, &name)?;
+    save_user_presets(&app, presets)
+}
+
+// Make a preset the current visualizer settings. Returns them so the caller can update
+// the visualizer and its controls.
+#[tauri::command]
+pub fn apply_visualizer_preset(app: tauri::AppHandle, name: String) -> Result<VisualizerSettings, String> {
+    let settings = find(&builtin_presets(), &name)
+        .or_else(|| find(&load_user_presets(&app), &name))
+        .map(|p| p.settings.clone())
+        .ok_or_else(|| format!("No preset named '{}'", name.trim()))?;
+
+    crate::config::save_visualizer_settings(app, settings.clone())?;
+    Ok(settings)
+}
+
+// Write presets (all user presets, or the named ones) to a JSON file in the Downloads
+// folder. The page can't choose the path, since it runs on a remote origin. Returns the
+// path written.
+#[tauri::command]
+pub fn export_visualizer_presets(app: tauri::AppHandle, names: Option<Vec<String>>) -> Result<String, String> {
+    let presets = load_user_presets(&app);
+    let presets = match names {
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                find(&presets, name)
+                    .cloned()
+                    .or_else(|| find(&builtin_presets(), name).cloned())
+                    .ok_or_else(|| format!("No preset named '{}'", name.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => presets,
+    };
+    if presets.is_empty() {
+        return Err("There are no presets to export".to_string());
+    }
+
+    let downloads = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to find the Downloads folder: {}", e))?;
+    let path = export_path(&downloads);
+
+    let json = export_json(presets)?;
+    crate::persist::write_atomic(&path, json.as_bytes())?;
+    log_info!("[Basitune] Exported visualizer presets to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+// Import presets from the contents of an exported file. Returns the names they were saved as.
+#[tauri::command]
+pub fn import_visualizer_presets(app: tauri::AppHandle, contents: String, overwrite: Option<bool>) -> Result<Vec<String>, String> {
+    let imported = parse_export(&contents)?;
+    let mut presets = load_user_presets(&app);
+    let names = merge(&mut presets, imported, overwrite.unwrap_or(false))?;
+    save_user_presets(&app, presets)?;
+    log_info!("[Basitune] Imported {} visualizer preset(s)", names.len());
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str) -> VisualizerPreset {
+        let mut preset = builtin_presets().remove(1);
+        preset.name = name.to_string();
+        preset
+    }
+
+    #[test]
+    fn builtin_presets_are_read_only() {
+        let mut presets = vec![];
+        let settings = builtin_presets()[0].settings.clone();
+        assert!(upsert(&mut presets, "classic", settings).is_err());
+        assert!(rename(&mut presets, "Classic", "Mine").is_err());
+        assert!(remove(&mut presets, "Neon Pulse").is_err());
+    }
+
+    #[test]
+    fn saving_an_existing_name_replaces_it() {
+        let mut presets = vec![user("Party")];
+        let settings = builtin_presets()[0].settings.clone();
+        upsert(&mut presets, " party ", settings.clone()).unwrap();
+
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].name, "party");
+        assert_eq!(presets[0].settings, settings);
+    }
+
+    #[test]
+    fn rename_rejects_taken_names() {
+        let mut presets = vec![user("Party"), user("Chill")];
+        assert!(rename(&mut presets, "Party", "chill").is_err());
+        assert!(rename(&mut presets, "Missing", "New").is_err());
+
+        // Changing only the case of a name is allowed
+        assert_eq!(rename(&mut presets, "Party", "PARTY").as_deref(), Ok("PARTY"));
+    }
+
+    #[test]
+    fn export_roundtrips_through_import() {
+        let json = export_json(vec![user("Party"), user("Chill")]).unwrap();
+        let imported = parse_export(&json).unwrap();
+        assert_eq!(imported, vec![user("Party"), user("Chill")]);
+
+        assert!(parse_export("{}").is_err());
+        assert!(parse_export(r#"{"format":"other","version":1,"presets":[]}"#).is_err());
+    }
+
+    #[test]
+    fn import_renames_conflicts_unless_overwriting() {
+        let mut presets = vec![user("Party")];
+        let names = merge(&mut presets, vec![user("Party"), user("Classic")], false).unwrap();
+        assert_eq!(names, vec!["Party (2)", "Classic (2)"]);
+        assert_eq!(presets.len(), 3);
+
+        let names = merge(&mut presets, vec![user("party")], true).unwrap();
+        assert_eq!(names, vec!["party"]);
+        assert_eq!(presets.len(), 3);
+    }
+
+    #[test]
+    fn renamed_imports_stay_within_the_name_limit() {
+        let long = "x".repeat(MAX_NAME_LEN);
+        let mut presets = vec![user(&long)];
+        let names = merge(&mut presets, vec![user(&long), user(&long)], false).unwrap();
+
+        assert_eq!(names[0], format!("{} (2)", "x".repeat(MAX_NAME_LEN - 4)));
+        assert_eq!(names[1], format!("{} (3)", "x".repeat(MAX_NAME_LEN - 4)));
+        assert!(names.iter().all(|name| validate_name(name).as_deref() == Ok(name.as_str())));
+    }
+
+    #[test]
+    fn exports_never_replace_earlier_ones() {
+        let dir = std::env::temp_dir().join(format!("basitune-presets-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = export_path(&dir);
+        assert_eq!(first, dir.join("basitune-visualizer-presets.json"));
+        std::fs::write(&first, "{}").unwrap();
+        let second = export_path(&dir);
+        assert_eq!(second, dir.join("basitune-visualizer-presets-2.json"));
+        std::fs::write(&second, "{}").unwrap();
+        assert_eq!(export_path(&dir), dir.join("basitune-visualizer-presets-3.json"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,206 @@
+// Named profiles. Each profile has its own config, content cache, window state, secrets,
+// and webview data (so its own YouTube Music login). The default profile uses the top
+// level of the app data directory, so data from before profiles existed stays where it
+// is. Other profiles live in profiles/<name>/.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_NAME_LEN: usize = 32;
+
+// Set by a profile switch so the restarted process opens the chosen profile, even if the
+// original command line named another one
+const SWITCH_ENV: &str = "BASITUNE_SWITCH_PROFILE";
+
+// Chosen once at startup; switching profiles restarts the app
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+// Replaces the platform app data directory when set with --data-dir
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ProfilesFile {
+    last_used: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<String>,
+}
+
+// Profile names become directory names, so keep them to a safe, portable set
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("Profile names must be 1 to {} characters long", MAX_NAME_LEN));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name));
+    }
+    Ok(name)
+}
+
+pub fn active() -> &'static str {
+    ACTIVE_PROFILE.get().map(String::as_str).unwrap_or(DEFAULT_PROFILE)
+}
+
+// Keep all app data (every profile, and webview data) under `dir`. Call before the app
+// starts; relative paths are resolved against the current directory.
+pub fn set_data_dir(dir: PathBuf) {
+    let dir = std::path::absolute(&dir).unwrap_or(dir);
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+fn root_dir(app_handle: &tauri::AppHandle) -> PathBuf {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return dir.clone();
+    }
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("Failed to get app data dir")
+}
+
+fn dir_for(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join("profiles").join(name)
+    }
+}
+
+fn profiles_file(root: &Path) -> PathBuf {
+    root.join("profiles.json")
+}
+
+// Directory holding the active profile's files
+pub fn data_dir(app_handle: &tauri::AppHandle) -> PathBuf {
+    dir_for(&root_dir(app_handle), active())
+}
+
+// Webview data for the active profile. The default profile keeps the platform's default
+// location so existing logins are preserved, unless --data-dir moved everything.
+pub fn webview_data_dir(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    (active() != DEFAULT_PROFILE || DATA_DIR_OVERRIDE.get().is_some())
+        .then(|| data_dir(app_handle).join("webview"))
+}
+
+// Pick the profile for this run and remember it for the next launch. Precedence: a
+// pending switch, then the requested profile (command line), then the last one used.
+// Must run before anything reads a profile path.
+pub fn init(app_handle: &tauri::AppHandle, requested: Option<String>) -> String {
+    let switched = std::env::var(SWITCH_ENV).ok();
+    std::env::remove_var(SWITCH_ENV);
+
+    let root = root_dir(app_handle);
+    let file: ProfilesFile = crate::persist::read_json(&profiles_file(&root)).unwrap_or_default();
+
+    let name = [switched, requested, file.last_used.clone()]
+        .into_iter()
+        .flatten()
+        .find_map(|name| match validate_name(&name) {
+            Ok(name) => Some(name),
+            Err(e) => {
+                log_warn!("[Basitune] {}", e);
+                None
+            }
+        })
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+    if let Err(e) = fs::create_dir_all(dir_for(&root, &name)) {
+        log_error!("[Basitune] Failed to create profile directory: {}", e);
+    }
+
+    if file.last_used.as_deref() != Some(name.as_str()) {
+        let updated = ProfilesFile { last_used: Some(name.clone()) };
+        if let Err(e) = crate::persist::write_json(&profiles_file(&root), &updated) {
+            log_error!("[Basitune] Failed to remember profile: {}", e);
+        }
+    }
+
+    let _ = ACTIVE_PROFILE.set(name.clone());
+    log_info!("[Basitune] Using profile '{}'", name);
+    name
+}
+
+// The default profile plus every profile directory that has been created
+pub fn list(app_handle: &tauri::AppHandle) -> Vec<String> {
+    let mut named: Vec<String> = fs::read_dir(root_dir(app_handle).join("profiles"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                .filter(|name| name != DEFAULT_PROFILE && validate_name(name).as_deref() == Ok(name.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    named.sort();
+
+    let mut profiles = vec![DEFAULT_PROFILE.to_string()];
+    profiles.extend(named);
+    profiles
+}
+
+// Restart into another profile, creating it if it doesn't exist yet. Config, cache and
+// webview data can't be swapped in place, so switching always goes through a restart.
+pub fn switch(app_handle: &tauri::AppHandle, name: &str) -> Result<(), String> {
+    let name = validate_name(name)?;
+    if name == active() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(dir_for(&root_dir(app_handle), &name))
+        .map_err(|e| format!("Failed to create profile '{}': {}", name, e))?;
+
+    log_info!("[Basitune] Switching to profile '{}'", name);
+    std::env::set_var(SWITCH_ENV, &name);
+
+    // restart() ends the process without a RunEvent::Exit, so the exit handler's cache
+    // flush never runs; write batched changes here or they are lost with the old profile
+    if let Some(cache) = app_handle.try_state::<crate::cache::ContentCache>() {
+        cache.flush();
+    }
+    app_handle.restart()
+}
+
+#[tauri::command]
+pub fn list_profiles(app: tauri::AppHandle) -> ProfileList {
+    ProfileList {
+        active: active().to_string(),
+        profiles: list(&app),
+    }
+}
+
+#[tauri::command]
+pub fn switch_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    switch(&app, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_normalized_and_checked() {
+        assert_eq!(validate_name(" Work ").as_deref(), Ok("work"));
+        assert_eq!(validate_name("kid_2").as_deref(), Ok("kid_2"));
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("a b").is_err());
+        assert!(validate_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn default_profile_uses_the_root_directory() {
+        let root = Path::new("/data");
+        assert_eq!(dir_for(root, DEFAULT_PROFILE), PathBuf::from("/data"));
+        assert_eq!(dir_for(root, "work"), PathBuf::from("/data/profiles/work"));
+    }
+}
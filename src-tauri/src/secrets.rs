@@ -0,0 +1,311 @@
+// Encrypted storage for API credentials.
+//
+// Each secret is sealed with AES-256-GCM and stored in `secrets.json`. The key is random,
+// generated on first use, and kept in `secrets.key` (readable only by the current user on
+// Unix). This keeps credentials out of config.json, its backups, and anything sent to the
+// page script. It does not protect against someone who can read the whole app data directory.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SECRETS_FILE_VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Secret {
+    OpenaiApiKey,
+    GeniusAccessToken,
+}
+
+impl Secret {
+    pub const ALL: [Secret; 2] = [Secret::OpenaiApiKey, Secret::GeniusAccessToken];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Secret::OpenaiApiKey => "openai_api_key",
+            Secret::GeniusAccessToken => "genius_access_token",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == name)
+    }
+
+    // Environment variable that overrides the stored value (for development)
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Secret::OpenaiApiKey => "OPENAI_API_KEY",
+            Secret::GeniusAccessToken => "GENIUS_ACCESS_TOKEN",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretsFile {
+    version: u32,
+    entries: BTreeMap<String, SealedSecret>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+// What the frontend may know about a credential: never the value itself
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStatus {
+    pub is_set: bool,
+    pub masked: Option<String>,
+    pub from_env: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialStatus {
+    pub openai_api_key: SecretStatus,
+    pub genius_access_token: SecretStatus,
+}
+
+pub struct SecretStore {
+    file_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl SecretStore {
+    pub fn for_app(app_handle: &tauri::AppHandle) -> Self {
+        Self::in_dir(&crate::profiles::data_dir(app_handle))
+    }
+
+    fn in_dir(dir: &Path) -> Self {
+        Self {
+            file_path: dir.join("secrets.json"),
+            key_path: dir.join("secrets.key"),
+        }
+    }
+
+    // Decrypt a stored secret. Errors are logged and read as "not set".
+    pub fn get(&self, secret: Secret) -> Option<String> {
+        let file: SecretsFile = crate::persist::read_json(&self.file_path)?;
+        let sealed = file.entries.get(secret.as_str())?;
+
+        // Without the key file there is nothing to decrypt with; don't create a new one here
+        let cipher = match self.read_key() {
+            Ok(Some(key)) => Aes256Gcm::new(&key),
+            Ok(None) => {
+                log_warn!("[Basitune] secrets.key is missing, stored {} can't be read", secret.as_str());
+                return None;
+            }
+            Err(e) => {
+                log_warn!("[Basitune] {}", e);
+                return None;
+            }
+        };
+
+        match open(&cipher, secret, sealed) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log_error!("[Basitune] Failed to decrypt {}: {}", secret.as_str(), e);
+                None
+            }
+        }
+    }
+
+    // Store or clear (None or blank) a single secret
+    pub fn set(&self, secret: Secret, value: Option<&str>) -> Result<(), String> {
+        let value = value.map(str::trim).filter(|v| !v.is_empty());
+        self.update(&[(secret, value)])
+    }
+
+    // Store several secrets in one write, e.g. when importing plaintext keys
+    pub fn set_all(&self, values: &[(Secret, String)]) -> Result<(), String> {
+        let values: Vec<(Secret, Option<&str>)> = values.iter().map(|(s, v)| (*s, Some(v.as_str()))).collect();
+        self.update(&values)
+    }
+
+    fn update(&self, values: &[(Secret, Option<&str>)]) -> Result<(), String> {
+        let mut file: SecretsFile = crate::persist::read_json(&self.file_path).unwrap_or_default();
+        file.version = SECRETS_FILE_VERSION;
+
+        let cipher = Aes256Gcm::new(&self.load_or_create_key()?);
+        for (secret, value) in values {
+            match value {
+                Some(value) => {
+                    file.entries.insert(secret.as_str().to_string(), seal(&cipher, *secret, value)?);
+                }
+                None => {
+                    file.entries.remove(secret.as_str());
+                }
+            }
+        }
+
+        crate::persist::write_json(&self.file_path, &file)
+    }
+
+    fn read_key(&self) -> Result<Option<Key<Aes256Gcm>>, String> {
+        match fs::read(&self.key_path) {
+            Ok(bytes) if bytes.len() == KEY_LEN => Ok(Some(*Key::<Aes256Gcm>::from_slice(&bytes))),
+            Ok(_) => Err("secrets.key is damaged".to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read secrets.key: {}", e)),
+        }
+    }
+
+    fn load_or_create_key(&self) -> Result<Key<Aes256Gcm>, String> {
+        if let Some(key) = self.read_key()? {
+            return Ok(key);
+        }
+
+        let key = Aes256Gcm::generate_key(OsRng);
+        crate::persist::write_atomic_private(&self.key_path, key.as_slice())?;
+        Ok(key)
+    }
+}
+
+// The secret's name is bound in as associated data, so a sealed value can't be
+// swapped into another entry
+fn seal(cipher: &Aes256Gcm, secret: Secret, value: &str) -> Result<SealedSecret, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: secret.as_str().as_bytes() })
+        .map_err(|_| format!("Failed to encrypt {}", secret.as_str()))?;
+
+    Ok(SealedSecret {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open(cipher: &Aes256Gcm, secret: Secret, sealed: &SealedSecret) -> Result<String, String> {
+    let nonce = BASE64.decode(&sealed.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = BASE64.decode(&sealed.ciphertext).map_err(|e| e.to_string())?;
+    if nonce.len() != 12 {
+        return Err("invalid nonce".to_string());
+    }
+
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: secret.as_str().as_bytes() })
+        .map_err(|_| "wrong key or damaged value".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+// Show just enough of a key to tell which one is configured, e.g. "••••3f9a"
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 12 {
+        return "••••".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("••••{}", tail)
+}
+
+fn env_value(secret: Secret) -> Option<String> {
+    std::env::var(secret.env_var()).ok()
+}
+
+// Resolve a credential: environment variable first (for development), then the store
+pub fn get_secret(app_handle: &tauri::AppHandle, secret: Secret) -> Option<String> {
+    env_value(secret).or_else(|| SecretStore::for_app(app_handle).get(secret))
+}
+
+fn status_of(store: &SecretStore, secret: Secret) -> SecretStatus {
+    let from_env = env_value(secret);
+    let value = from_env.clone().or_else(|| store.get(secret));
+    SecretStatus {
+        is_set: value.is_some(),
+        masked: value.as_deref().map(mask),
+        from_env: from_env.is_some(),
+    }
+}
+
+pub fn credential_status(app_handle: &tauri::AppHandle) -> CredentialStatus {
+    let store = SecretStore::for_app(app_handle);
+    CredentialStatus {
+        openai_api_key: status_of(&store, Secret::OpenaiApiKey),
+        genius_access_token: status_of(&store, Secret::GeniusAccessToken),
+    }
+}
+
+// Store a credential from the settings UI. A missing or blank value clears it.
+#[tauri::command]
+pub fn set_api_secret(app: tauri::AppHandle, name: Secret, value: Option<String>) -> Result<SecretStatus, String> {
+    let store = SecretStore::for_app(&app);
+    store.set(name, value.as_deref())?;
+    Ok(status_of(&store, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> SecretStore {
+        let dir = std::env::temp_dir().join(format!("basitune-secrets-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        SecretStore::in_dir(&dir)
+    }
+
+    #[test]
+    fn set_then_get_roundtrip() {
+        let store = temp_store("roundtrip");
+        store.set(Secret::OpenaiApiKey, Some("sk-proj-abcdef123456")).unwrap();
+
+        assert_eq!(store.get(Secret::OpenaiApiKey).as_deref(), Some("sk-proj-abcdef123456"));
+        assert_eq!(store.get(Secret::GeniusAccessToken), None);
+    }
+
+    #[test]
+    fn secrets_file_holds_no_plaintext() {
+        let store = temp_store("no-plaintext");
+        store.set(Secret::GeniusAccessToken, Some("genius-token-value")).unwrap();
+
+        let contents = fs::read_to_string(&store.file_path).unwrap();
+        assert!(!contents.contains("genius-token-value"));
+    }
+
+    #[test]
+    fn blank_value_clears_secret() {
+        let store = temp_store("clear");
+        store.set(Secret::OpenaiApiKey, Some("sk-proj-abcdef123456")).unwrap();
+        store.set(Secret::OpenaiApiKey, Some("  ")).unwrap();
+
+        assert_eq!(store.get(Secret::OpenaiApiKey), None);
+    }
+
+    #[test]
+    fn sealed_value_is_bound_to_its_name() {
+        let store = temp_store("aad");
+        store.set(Secret::OpenaiApiKey, Some("sk-proj-abcdef123456")).unwrap();
+
+        // Copy the sealed OpenAI key into the Genius slot
+        let mut file: SecretsFile = crate::persist::read_json(&store.file_path).unwrap();
+        let sealed = file.entries["openai_api_key"].clone();
+        file.entries.insert("genius_access_token".to_string(), sealed);
+        crate::persist::write_json(&store.file_path, &file).unwrap();
+
+        assert_eq!(store.get(Secret::GeniusAccessToken), None);
+    }
+
+    #[test]
+    fn missing_key_file_reads_as_unset() {
+        let store = temp_store("no-key");
+        store.set(Secret::OpenaiApiKey, Some("sk-proj-abcdef123456")).unwrap();
+        fs::remove_file(&store.key_path).unwrap();
+
+        assert_eq!(store.get(Secret::OpenaiApiKey), None);
+        assert!(!store.key_path.exists(), "reading must not create a new key");
+    }
+
+    #[test]
+    fn mask_shows_only_last_four() {
+        assert_eq!(mask("sk-proj-abcdef123456"), "••••3456");
+        assert_eq!(mask("short"), "••••");
+    }
+}
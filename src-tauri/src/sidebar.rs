@@ -1,4 +1,3 @@
-use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::{Manager, PhysicalPosition, PhysicalSize};
@@ -12,20 +11,9 @@ impl WindowStateManager {
     pub fn new(app_handle: tauri::AppHandle) -> Self {
         let state_path = Self::get_state_path(&app_handle);
         
-        let loaded_state = match fs::read_to_string(&state_path) {
-            Ok(contents) => {
-                match serde_json::from_str::<crate::config::WindowState>(&contents) {
-                    Ok(state) => state,
-                    Err(e) => {
-                        eprintln!("[Basitune] Failed to parse window state: {}", e);
-                        crate::config::WindowState::default()
-                    }
-                }
-            }
-            Err(_) => {
-                crate::config::WindowState::default()
-            }
-        };
+        let mut loaded_state = crate::persist::read_json::<crate::config::WindowState>(&state_path)
+            .unwrap_or_default();
+        crate::validation::sanitize_window_state(&mut loaded_state);
         
         Self {
             state: Mutex::new(loaded_state),
@@ -34,9 +22,19 @@ impl WindowStateManager {
     }
     
     fn get_state_path(app_handle: &tauri::AppHandle) -> PathBuf {
-        let app_data_dir = app_handle.path().app_data_dir()
-            .expect("Failed to get app data directory");
-        app_data_dir.join("window-state.json")
+        crate::profiles::data_dir(app_handle).join("window-state.json")
+    }
+    
+    // Delete the saved state (and its backup) so the next load starts from defaults
+    pub fn reset_saved_state(app_handle: &tauri::AppHandle) {
+        let state_path = Self::get_state_path(app_handle);
+        for path in [crate::persist::backup_path(&state_path), state_path] {
+            match std::fs::remove_file(&path) {
+                Ok(()) => log_info!("[Basitune] Removed {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log_error!("[Basitune] Failed to remove {}: {}", path.display(), e),
+            }
+        }
     }
     
     pub fn get(&self) -> crate::config::WindowState {
@@ -49,10 +47,30 @@ impl WindowStateManager {
     {
         let mut state = self.state.lock().unwrap();
         f(&mut state);
+        // Keep sizes in range whichever command changed them
+        crate::validation::sanitize_window_state(&mut state);
         drop(state);  // Explicitly drop the lock
         // Don't save to disk
     }
     
+    // Pick up an outside edit of window-state.json. Returns true if the state changed;
+    // our own saves match the in-memory state and are ignored.
+    pub fn reload_from_disk(&self) -> Result<bool, String> {
+        let state_path = Self::get_state_path(&self.app_handle);
+        let mut loaded = match crate::persist::read_json_strict::<crate::config::WindowState>(&state_path)? {
+            Some(loaded) => loaded,
+            None => return Ok(false),
+        };
+        crate::validation::sanitize_window_state(&mut loaded);
+
+        let mut state = self.state.lock().unwrap();
+        if *state == loaded {
+            return Ok(false);
+        }
+        *state = loaded;
+        Ok(true)
+    }
+    
     pub fn save_silent(&self) {
         let state = self.state.lock().unwrap();
         self.save_to_disk(&state);
@@ -61,17 +79,8 @@ impl WindowStateManager {
     fn save_to_disk(&self, state: &crate::config::WindowState) {
         let state_path = Self::get_state_path(&self.app_handle);
         
-        // Ensure directory exists
-        if let Some(parent) = state_path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        
-        if let Ok(json) = serde_json::to_string_pretty(state) {
-            if let Err(e) = fs::write(&state_path, json) {
-                eprintln!("[Basitune] Failed to save window state: {}", e);
-            }
-        } else {
-            eprintln!("[Basitune] Failed to serialize window state");
+        if let Err(e) = crate::persist::write_json(&state_path, state) {
+            log_error!("[Basitune] Failed to save window state: {}", e);
         }
     }
 }
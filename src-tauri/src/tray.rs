@@ -1,6 +1,31 @@
 use crate::playback::PlaybackState;
 use tauri::Manager;
 
+// Menu item ids for profile entries are this prefix followed by the profile name
+pub const PROFILE_MENU_PREFIX: &str = "profile:";
+
+// "Profile" submenu listing every profile, with the active one checked
+pub fn profile_submenu(app: &tauri::AppHandle) -> Result<tauri::menu::Submenu<tauri::Wry>, String> {
+    use tauri::menu::{CheckMenuItem, IsMenuItem, Submenu};
+    
+    let active = crate::profiles::active();
+    let items = crate::profiles::list(app)
+        .into_iter()
+        .map(|name| {
+            let id = format!("{}{}", PROFILE_MENU_PREFIX, name);
+            CheckMenuItem::with_id(app, id, &name, true, name == active, None::<&str>)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let item_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+        .collect();
+    
+    Submenu::with_items(app, format!("Profile: {}", active), true, &item_refs)
+        .map_err(|e| e.to_string())
+}
+
 pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), String> {
     use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
     
@@ -20,6 +45,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
         .map_err(|e| e.to_string())?;
     let next_track = MenuItem::with_id(app, "next_track", "Next Track", true, None::<&str>)
         .map_err(|e| e.to_string())?;
+    let profile_menu = profile_submenu(app)?;
     
     // Build menu based on state
     let menu = if let Some((title, artist)) = current_song {
@@ -47,6 +73,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &stop,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             },
@@ -66,6 +93,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &stop,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             },
@@ -82,6 +110,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &play,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             }
@@ -103,6 +132,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &stop,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             },
@@ -120,6 +150,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &stop,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             },
@@ -134,6 +165,7 @@ pub fn rebuild_tray_menu(app: &tauri::AppHandle, state: &str) -> Result<(), Stri
                     &play,
                     &next_track,
                     &separator2,
+                    &profile_menu,
                     &quit
                 ]).map_err(|e| e.to_string())?
             }
@@ -88,7 +88,7 @@ pub async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
         .map_err(|e| format!("Failed to check for updates: {}", e))?
         .ok_or_else(|| "No update available".to_string())?;
     
-    println!("[Basitune] Downloading update {} -> {}", update.current_version, update.version);
+    log_info!("[Basitune] Downloading update {} -> {}", update.current_version, update.version);
     
     // Get window for progress updates
     let window = app.get_webview_window("main");
@@ -105,11 +105,11 @@ pub async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
             }
         },
         || {
-            println!("[Basitune] Update downloaded and ready to install");
+            log_info!("[Basitune] Update downloaded and ready to install");
         }
     ).await
     .map_err(|e| format!("Failed to install update: {}", e))?;
     
-    println!("[Basitune] Update installed successfully");
+    log_info!("[Basitune] Update installed successfully");
     Ok(())
 }
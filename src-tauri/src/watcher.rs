@@ -0,0 +1,145 @@
+// Watches the active profile's settings files so edits made outside the app
+// take effect without a restart. The directory is watched rather than the files, since
+// atomic saves (ours and most editors') replace the file instead of modifying it.
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+const CONFIG_FILE: &str = "config.json";
+const WINDOW_STATE_FILE: &str = "window-state.json";
+const SECRETS_FILE: &str = "secrets.json";
+
+// Editors often save in several steps (truncate, write, rename); wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Payload of the config-changed event. Listeners re-read what they need with get_config
+// or the sidebar getters.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChanged {
+    pub file: &'static str,
+    pub sections: Vec<&'static str>,
+}
+
+pub fn start(app_handle: tauri::AppHandle) {
+    let dir = crate::profiles::data_dir(&app_handle);
+    let _ = std::fs::create_dir_all(&dir);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log_warn!("[Basitune] Settings watcher disabled: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        log_warn!("[Basitune] Settings watcher disabled: {}", e);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        // The watcher stops when dropped, so it lives as long as this thread
+        let _watcher = watcher;
+        let mut config = crate::config::load_config(&app_handle);
+
+        while let Ok(event) = rx.recv() {
+            let mut files = BTreeSet::new();
+            collect(&mut files, event);
+            while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+                collect(&mut files, event);
+            }
+
+            if files.contains(CONFIG_FILE) {
+                reload_config(&app_handle, &mut config);
+            }
+            if files.contains(WINDOW_STATE_FILE) {
+                reload_window_state(&app_handle);
+            }
+            if files.contains(SECRETS_FILE) {
+                emit(&app_handle, SECRETS_FILE, vec!["credentials"]);
+            }
+        }
+    });
+
+    log_info!("[Basitune] Watching settings files in {}", dir.display());
+}
+
+fn collect(files: &mut BTreeSet<&'static str>, event: notify::Result<notify::Event>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            log_error!("[Basitune] Settings watcher error: {}", e);
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    for path in &event.paths {
+        let name = path.file_name().and_then(|name| name.to_str());
+        if let Some(file) = [CONFIG_FILE, WINDOW_STATE_FILE, SECRETS_FILE].into_iter().find(|f| Some(*f) == name) {
+            files.insert(file);
+        }
+    }
+}
+
+fn reload_config(app_handle: &tauri::AppHandle, current: &mut crate::config::ApiConfig) {
+    let updated = match crate::config::reload_config(app_handle) {
+        Ok(updated) => updated,
+        Err(e) => {
+            // Keep running with the last good settings until the file is fixed
+            log_warn!("[Basitune] Ignoring change to config.json: {}", e);
+            return;
+        }
+    };
+
+    let sections = crate::config::changed_sections(current, &updated);
+    if sections.is_empty() {
+        return;
+    }
+
+    if sections.contains(&"cache") {
+        if let Some(cache) = app_handle.try_state::<crate::cache::ContentCache>() {
+            cache.set_policy(crate::cache::CachePolicy::from_config(&updated));
+        }
+    }
+
+    log_info!("[Basitune] Reloaded config.json ({} changed)", sections.join(", "));
+    *current = updated;
+    emit(app_handle, CONFIG_FILE, sections);
+}
+
+fn reload_window_state(app_handle: &tauri::AppHandle) {
+    let manager = match app_handle.try_state::<crate::sidebar::WindowStateManager>() {
+        Some(manager) => manager,
+        None => return,
+    };
+
+    match manager.reload_from_disk() {
+        Ok(true) => {
+            log_info!("[Basitune] Reloaded window-state.json");
+            emit(app_handle, WINDOW_STATE_FILE, vec!["window"]);
+        }
+        Ok(false) => {}
+        Err(e) => log_warn!("[Basitune] Ignoring change to window-state.json: {}", e),
+    }
+}
+
+// Emit config-changed, and hand it to the injected sidebar script directly as well, since
+// the remote page isn't granted the event API
+fn emit(app_handle: &tauri::AppHandle, file: &'static str, sections: Vec<&'static str>) {
+    let payload = ConfigChanged { file, sections };
+
+    if let Err(e) = app_handle.emit("config-changed", payload.clone()) {
+        log_error!("[Basitune] Failed to emit config-changed: {}", e);
+    }
+
+    if let (Some(window), Ok(json)) = (app_handle.get_webview_window("main"), serde_json::to_string(&payload)) {
+        let _ = window.eval(format!("window.basituneConfigChanged && window.basituneConfigChanged({})", json));
+    }
+}
@@ -170,6 +170,17 @@
                     <div id="basitune-artist-tab" class="basitune-tab-content active">
                         <div id="basitune-song-context">
                         </div>
+                        <div id="basitune-song-chat" style="display: none;">
+                            <h5>Ask about this song</h5>
+                            <div class="basitune-chat-messages"></div>
+                            <textarea id="basitune-chat-input" rows="2" maxlength="2000" placeholder="e.g. What's the story behind the second verse?" style="width: 100%; padding: 8px 10px; margin-bottom: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: inherit; resize: vertical; box-sizing: border-box;"></textarea>
+                            <div style="display: flex; gap: 8px;">
+                                <button id="basitune-chat-send" style="flex: 1; padding: 6px 12px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;">Ask</button>
+                                <button id="basitune-chat-export" style="padding: 6px 12px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Save this conversation to a file in Downloads">Export</button>
+                                <button id="basitune-chat-clear" style="padding: 6px 12px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Delete this conversation">Clear</button>
+                            </div>
+                            <div id="basitune-chat-status" style="margin-top: 8px; font-size: 12px; color: rgba(255, 255, 255, 0.6); min-height: 16px;"></div>
+                        </div>
                         <div id="basitune-artist-bio">
                             <p class="basitune-placeholder">Play a song to see artist information</p>
                         </div>
@@ -208,6 +219,32 @@
                                 </select>
                             </div>
 
+                            <!-- Presets Section -->
+                            <div class="basitune-viz-section">
+                                <div class="basitune-viz-section-header" data-section="presets">
+                                    <span>💾 Presets</span>
+                                    <span class="basitune-viz-section-toggle">▼</span>
+                                </div>
+                                <div class="basitune-viz-section-content" id="basitune-viz-presets-section">
+                                    <div style="display: flex; gap: 8px; margin-bottom: 8px;">
+                                        <select id="basitune-viz-preset" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1) !important; border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff !important; font-size: 13px; cursor: pointer; -webkit-appearance: none; -moz-appearance: none; appearance: none; box-sizing: border-box; flex: 1;"></select>
+                                        <button id="basitune-viz-preset-apply" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer; flex: 0 0 auto; padding: 8px 14px;">Apply</button>
+                                    </div>
+                                    <input type="text" id="basitune-viz-preset-name" placeholder="Preset name" maxlength="48" style="width: 100%; padding: 8px 10px; margin-bottom: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; box-sizing: border-box;" />
+                                    <div style="display: flex; gap: 8px; margin-bottom: 8px;">
+                                        <button id="basitune-viz-preset-save" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Save the current settings under this name">Save</button>
+                                        <button id="basitune-viz-preset-rename" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Rename the selected preset">Rename</button>
+                                        <button id="basitune-viz-preset-delete" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Delete the selected preset">Delete</button>
+                                    </div>
+                                    <div style="display: flex; gap: 8px;">
+                                        <button id="basitune-viz-preset-export" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Save your presets to a file in Downloads">Export</button>
+                                        <button id="basitune-viz-preset-import" style="flex: 1; padding: 8px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); color: rgba(255, 255, 255, 0.9); font-size: 12px; font-weight: 500; border-radius: 6px; cursor: pointer;" title="Add presets from an exported file">Import</button>
+                                        <input type="file" id="basitune-viz-preset-file" accept=".json,application/json" style="display: none;" />
+                                    </div>
+                                    <div id="basitune-viz-preset-status" style="margin-top: 8px; font-size: 12px; color: rgba(255, 255, 255, 0.6); min-height: 16px;"></div>
+                                </div>
+                            </div>
+
                             <!-- Colors Section -->
                             <div class="basitune-viz-section">
                                 <div class="basitune-viz-section-header" data-section="colors">
@@ -312,6 +349,68 @@
                                 </small>
                             </div>
                             
+                            <h3 style="margin-top: 30px; margin-bottom: 20px; color: #fff; font-size: 18px; border-top: 1px solid rgba(255, 255, 255, 0.1); padding-top: 24px;">AI Provider</h3>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    API Base URL
+                                </label>
+                                <input type="text" id="basitune-ai-base-url" placeholder="https://api.openai.com/v1" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box;" />
+                                <small style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px;">
+                                    Any OpenAI-compatible server, e.g. http://localhost:11434/v1 for Ollama. Leave blank for OpenAI. The API key above is sent to this server if set
+                                </small>
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Model
+                                </label>
+                                <input type="text" id="basitune-ai-model" placeholder="gpt-4o-mini" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box;" />
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Extra Headers
+                                </label>
+                                <textarea id="basitune-ai-headers" rows="3" placeholder="X-Api-Version: 2" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box; resize: vertical;"></textarea>
+                                <small style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px;">
+                                    One "Name: value" per line, sent with every AI request. Stored unencrypted in config.json
+                                </small>
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Output Language
+                                </label>
+                                <input type="text" id="basitune-ai-language" placeholder="English" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box;" />
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Artist Info Prompt
+                                </label>
+                                <textarea id="basitune-ai-prompt-artist" rows="4" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box; resize: vertical;"></textarea>
+                                <small style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px;">
+                                    Placeholders: {artist}, {language}. Leave blank for the built-in prompt
+                                </small>
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Song Context Prompt
+                                </label>
+                                <textarea id="basitune-ai-prompt-song" rows="4" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box; resize: vertical;"></textarea>
+                                <small style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px;">
+                                    Placeholders: {artist}, {title}, {album}, {language}. Leave blank for the built-in prompt
+                                </small>
+                            </div>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: block; color: rgba(255, 255, 255, 0.9); font-weight: 500; margin-bottom: 8px; font-size: 13px;">
+                                    Spending Limits (USD)
+                                </label>
+                                <div style="display: flex; gap: 8px;">
+                                    <input type="number" id="basitune-ai-daily-limit" min="0" step="0.01" placeholder="Daily" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box;" />
+                                    <input type="number" id="basitune-ai-monthly-limit" min="0" step="0.01" placeholder="Monthly" style="width: 100%; padding: 10px; background: rgba(255, 255, 255, 0.1); border: 1px solid rgba(255, 255, 255, 0.2); border-radius: 6px; color: #fff; font-size: 13px; font-family: monospace; box-sizing: border-box;" />
+                                </div>
+                                <small id="basitune-ai-usage" style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px;">
+                                    Leave blank for no limit. Once a limit is reached, only cached AI content is shown
+                                </small>
+                            </div>
+                            
                             <h3 style="margin-top: 30px; margin-bottom: 20px; color: #fff; font-size: 18px; border-top: 1px solid rgba(255, 255, 255, 0.1); padding-top: 24px;">System Tray</h3>
                             <div style="margin-bottom: 24px;">
                                 <label style="display: flex; align-items: center; color: rgba(255, 255, 255, 0.9); font-size: 13px; cursor: pointer; user-select: none;">
@@ -334,6 +433,17 @@
                                 </small>
                             </div>
                             
+                            <h3 style="margin-top: 30px; margin-bottom: 20px; color: #fff; font-size: 18px; border-top: 1px solid rgba(255, 255, 255, 0.1); padding-top: 24px;">Discord</h3>
+                            <div style="margin-bottom: 24px;">
+                                <label style="display: flex; align-items: center; color: rgba(255, 255, 255, 0.9); font-size: 13px; cursor: pointer; user-select: none;">
+                                    <input type="checkbox" id="basitune-enable-discord" style="margin-right: 10px; width: 18px; height: 18px; cursor: pointer; accent-color: #ff0000;" />
+                                    <span>Show the current song in Discord</span>
+                                </label>
+                                <small style="color: rgba(255, 255, 255, 0.6); font-size: 11px; display: block; margin-top: 6px; margin-left: 28px;">
+                                    Share what you're listening to as your Discord status while Discord is running.
+                                </small>
+                            </div>
+                            
                             <h3 style="margin-top: 30px; margin-bottom: 20px; color: #fff; font-size: 18px; border-top: 1px solid rgba(255, 255, 255, 0.1); padding-top: 24px;">Playback</h3>
                             <div style="margin-bottom: 24px;">
                                 <label style="display: flex; align-items: center; color: rgba(255, 255, 255, 0.9); font-size: 13px; cursor: pointer; user-select: none;">
@@ -705,6 +815,51 @@
                 letter-spacing: 0.5px;
             }
             
+            .basitune-artist-facts,
+            .basitune-artist-members,
+            .basitune-artist-label {
+                color: rgba(255, 255, 255, 0.6);
+                font-size: 13px;
+                margin: -8px 0 10px 0;
+            }
+            
+            .basitune-artist-members {
+                margin: 10px 0 0 0;
+            }
+            
+            .basitune-artist-similar {
+                margin-top: 12px;
+            }
+            
+            .basitune-artist-similar .basitune-artist-label {
+                margin: 0 0 6px 0;
+            }
+            
+            .basitune-chips {
+                display: flex;
+                flex-wrap: wrap;
+                gap: 6px;
+                margin-bottom: 10px;
+            }
+            
+            .basitune-chip {
+                padding: 3px 10px;
+                border-radius: 12px;
+                background: rgba(255, 255, 255, 0.08);
+                border: 1px solid rgba(255, 255, 255, 0.12);
+                color: rgba(255, 255, 255, 0.85);
+                font-size: 12px;
+                text-decoration: none;
+                cursor: pointer;
+                transition: all 0.2s;
+            }
+            
+            .basitune-chip:hover {
+                background: rgba(255, 0, 0, 0.2);
+                border-color: rgba(255, 0, 0, 0.4);
+                color: #fff;
+            }
+            
             #basitune-song-context {
                 color: rgba(255, 255, 255, 0.85);
                 line-height: 1.7;
@@ -737,6 +892,55 @@
                 letter-spacing: 0.3px;
             }
             
+            #basitune-song-chat {
+                margin-bottom: 20px;
+                padding: 16px;
+                background: rgba(255, 255, 255, 0.03);
+                border-radius: 12px;
+                animation: basitune-fadeIn 0.5s ease-out;
+            }
+            
+            #basitune-song-chat h5 {
+                color: #fff;
+                margin: 0 0 12px 0;
+                font-size: 15px;
+                font-weight: 600;
+                letter-spacing: 0.3px;
+            }
+            
+            .basitune-chat-messages {
+                display: flex;
+                flex-direction: column;
+                gap: 8px;
+                max-height: 320px;
+                overflow-y: auto;
+                margin-bottom: 8px;
+            }
+            
+            .basitune-chat-messages:empty {
+                display: none;
+            }
+            
+            .basitune-chat-message {
+                padding: 8px 12px;
+                border-radius: 10px;
+                font-size: 13px;
+                line-height: 1.5;
+                white-space: pre-wrap;
+                color: rgba(255, 255, 255, 0.85);
+            }
+            
+            .basitune-chat-user {
+                align-self: flex-end;
+                max-width: 85%;
+                background: rgba(255, 0, 0, 0.18);
+            }
+            
+            .basitune-chat-assistant {
+                align-self: flex-start;
+                background: rgba(255, 255, 255, 0.07);
+            }
+            
             #basitune-lyrics-content {
                 color: rgba(255, 255, 255, 0.85);
                 font-family: 'Roboto', sans-serif;
@@ -1390,6 +1594,9 @@
             });
         });
         
+        // Song chat
+        setupSongChat();
+        
         // Settings save button
         const saveSettingsBtn = document.getElementById('basitune-save-settings');
         if (saveSettingsBtn) {
@@ -1738,47 +1945,225 @@
     // Settings functions
     async function loadSettings() {
         try {
-            const config = await window.__TAURI__.core.invoke('get_config');
+            const { credentials, general, ai } = await window.__TAURI__.core.invoke('get_config');
             
             const openaiInput = document.getElementById('basitune-openai-key');
             const geniusInput = document.getElementById('basitune-genius-token');
             const closeToTrayCheckbox = document.getElementById('basitune-close-to-tray');
             const enableNotificationsCheckbox = document.getElementById('basitune-enable-notifications');
             const resumePlaybackCheckbox = document.getElementById('basitune-resume-playback');
+            const enableDiscordCheckbox = document.getElementById('basitune-enable-discord');
             
-            if (openaiInput && config.openai_api_key) {
-                openaiInput.value = config.openai_api_key;
-            }
-            if (geniusInput && config.genius_access_token) {
-                geniusInput.value = config.genius_access_token;
-            }
-            if (closeToTrayCheckbox && config.close_to_tray !== undefined) {
-                closeToTrayCheckbox.checked = config.close_to_tray;
+            // Keys never leave the backend; show which one is saved and leave the field for a new one
+            showSecretStatus(openaiInput, credentials.openai_api_key);
+            showSecretStatus(geniusInput, credentials.genius_access_token);
+            if (closeToTrayCheckbox && general.close_to_tray !== undefined) {
+                closeToTrayCheckbox.checked = general.close_to_tray;
             }
-            if (enableNotificationsCheckbox && config.enable_notifications !== undefined) {
-                enableNotificationsCheckbox.checked = config.enable_notifications;
+            if (enableNotificationsCheckbox && general.enable_notifications !== undefined) {
+                enableNotificationsCheckbox.checked = general.enable_notifications;
             }
-            if (resumePlaybackCheckbox && config.resume_playback_on_startup !== undefined) {
-                resumePlaybackCheckbox.checked = config.resume_playback_on_startup;
+            if (resumePlaybackCheckbox && general.resume_playback_on_startup !== undefined) {
+                resumePlaybackCheckbox.checked = general.resume_playback_on_startup;
             } else if (resumePlaybackCheckbox) {
                 // Default to true if not set
                 resumePlaybackCheckbox.checked = true;
             }
+            if (enableDiscordCheckbox) {
+                // Default to true if not set
+                enableDiscordCheckbox.checked = general.enable_discord_presence !== false;
+            }
+            
+            const baseUrlInput = document.getElementById('basitune-ai-base-url');
+            const modelInput = document.getElementById('basitune-ai-model');
+            const headersInput = document.getElementById('basitune-ai-headers');
+            if (baseUrlInput) baseUrlInput.value = ai.base_url || '';
+            if (modelInput) modelInput.value = ai.model || '';
+            if (headersInput) {
+                headersInput.value = Object.entries(ai.headers || {})
+                    .map(([name, value]) => `${name}: ${value}`)
+                    .join('\n');
+            }
+            const languageInput = document.getElementById('basitune-ai-language');
+            const artistPromptInput = document.getElementById('basitune-ai-prompt-artist');
+            const songPromptInput = document.getElementById('basitune-ai-prompt-song');
+            if (languageInput) languageInput.value = ai.language || '';
+            if (artistPromptInput) artistPromptInput.value = ai.prompts?.artist_info || '';
+            if (songPromptInput) songPromptInput.value = ai.prompts?.song_context || '';
+            const dailyLimitInput = document.getElementById('basitune-ai-daily-limit');
+            const monthlyLimitInput = document.getElementById('basitune-ai-monthly-limit');
+            if (dailyLimitInput) dailyLimitInput.value = ai.budget?.daily_limit_usd ?? '';
+            if (monthlyLimitInput) monthlyLimitInput.value = ai.budget?.monthly_limit_usd ?? '';
+            loadAiUsage();
         } catch (error) {
             console.error('[Basitune] Failed to load settings:', error);
         }
     }
     
+    // Show today's and this month's AI spending under the limit inputs
+    async function loadAiUsage() {
+        const usageText = document.getElementById('basitune-ai-usage');
+        if (!usageText) return;
+        try {
+            const usage = await window.__TAURI__.core.invoke('get_ai_usage', { days: 1 });
+            const describe = (totals) => {
+                const tokens = (totals.prompt_tokens + totals.completion_tokens).toLocaleString();
+                return `$${totals.cost_usd.toFixed(4)} (${tokens} tokens)`;
+            };
+            let text = `Today: ${describe(usage.today)} · This month: ${describe(usage.month)}`;
+            if (usage.exhausted) {
+                text += ' · Limit reached, showing cached content only';
+            }
+            usageText.textContent = text;
+        } catch (error) {
+            console.error('[Basitune] Failed to load AI usage:', error);
+        }
+    }
+    
+    // Blank number inputs clear a setting
+    function numberOrNull(id) {
+        const value = document.getElementById(id)?.value.trim();
+        return value ? Number(value) : null;
+    }
+    
+    // Settings files were edited outside the app; the backend re-validated them first
+    async function handleConfigChanged(change) {
+        console.log('[Basitune] Settings changed on disk:', change.file, change.sections);
+        try {
+            if (change.file === 'window-state.json') {
+                const savedWidth = await window.__TAURI__.core.invoke('get_sidebar_width');
+                const savedFontSize = await window.__TAURI__.core.invoke('get_sidebar_font_size');
+                if (savedWidth >= 280 && savedWidth <= 800) {
+                    sidebarWidth = savedWidth;
+                    if (sidebarVisible) {
+                        setSidebarWidth(savedWidth);
+                    }
+                }
+                if (savedFontSize >= 10 && savedFontSize <= 24) {
+                    sidebarFontSize = savedFontSize;
+                    applyFontSize();
+                }
+                return;
+            }
+            
+            // Refresh the settings form (general options and key status)
+            await loadSettings();
+            
+            if (change.sections.includes('visualizer') && window.basituneVisualizer) {
+                const { visualizer } = await window.__TAURI__.core.invoke('get_config');
+                const keys = {
+                    style: 'style',
+                    color: 'color',
+                    sensitivity: 'sensitivity',
+                    color_palette: 'colorPalette',
+                    animation_speed: 'animationSpeed',
+                    glow_enabled: 'glowEnabled',
+                    glow_intensity: 'glowIntensity',
+                    bar_spacing: 'barSpacing',
+                    particle_count: 'particleCount',
+                    line_thickness: 'lineThickness'
+                };
+                const settings = {};
+                for (const [field, key] of Object.entries(keys)) {
+                    if (visualizer[field] !== null && visualizer[field] !== undefined) {
+                        settings[key] = visualizer[field];
+                    }
+                }
+                window.basituneVisualizer.updateSettings(settings);
+            }
+        } catch (error) {
+            console.error('[Basitune] Failed to apply changed settings:', error);
+        }
+    }
+    
+    function showSecretStatus(input, status) {
+        if (!input) return;
+        if (!input.dataset.defaultPlaceholder) {
+            input.dataset.defaultPlaceholder = input.placeholder;
+        }
+        input.value = '';
+        if (status && status.is_set) {
+            const source = status.from_env ? 'set by environment' : 'saved';
+            input.placeholder = `${status.masked} (${source}, leave blank to keep)`;
+        } else {
+            input.placeholder = input.dataset.defaultPlaceholder;
+        }
+    }
+    
+    // Settings commands fail with { message, fields: [{ field, message }] }. Show each field
+    // error next to its input; `inputs` maps setting paths like 'visualizer.color' to input ids.
+    // Errors shown earlier for the same inputs are cleared, so call with null after a save.
+    function showFieldErrors(error, inputs) {
+        for (const field of Object.keys(inputs)) {
+            document.querySelectorAll(`.basitune-field-error[data-field="${field}"]`).forEach(el => el.remove());
+        }
+        for (const { field, message } of (error && error.fields) || []) {
+            const input = document.getElementById(inputs[field]);
+            if (!input) continue;
+            const note = document.createElement('div');
+            note.className = 'basitune-field-error';
+            note.dataset.field = field;
+            note.style.cssText = 'color: #ff6b6b; font-size: 12px; margin-top: 4px;';
+            note.textContent = message;
+            (input.closest('label') || input).insertAdjacentElement('afterend', note);
+        }
+    }
+    
+    function settingsErrorMessage(error) {
+        return (error && error.message) || String(error);
+    }
+    
+    const SETTINGS_TAB_INPUTS = {
+        'general.close_to_tray': 'basitune-close-to-tray',
+        'general.enable_notifications': 'basitune-enable-notifications',
+        'general.resume_playback_on_startup': 'basitune-resume-playback',
+        'general.enable_discord_presence': 'basitune-enable-discord',
+        'ai.base_url': 'basitune-ai-base-url',
+        'ai.model': 'basitune-ai-model',
+        'ai.headers': 'basitune-ai-headers',
+        'ai.language': 'basitune-ai-language',
+        'ai.prompts.artist_info': 'basitune-ai-prompt-artist',
+        'ai.prompts.song_context': 'basitune-ai-prompt-song',
+        'ai.budget.daily_limit_usd': 'basitune-ai-daily-limit',
+        'ai.budget.monthly_limit_usd': 'basitune-ai-monthly-limit'
+    };
+    
+    // "Name: value" lines from the extra headers box; blank means none
+    function parseHeaderLines(text) {
+        const headers = {};
+        for (const line of text.split('\n')) {
+            if (!line.trim()) continue;
+            const colon = line.indexOf(':');
+            const name = (colon === -1 ? line : line.slice(0, colon)).trim();
+            headers[name] = colon === -1 ? '' : line.slice(colon + 1).trim();
+        }
+        return Object.keys(headers).length ? headers : null;
+    }
+    
+    const VISUALIZER_SETTING_INPUTS = {
+        'visualizer.style': 'basitune-viz-style',
+        'visualizer.color': 'basitune-viz-color',
+        'visualizer.sensitivity': 'basitune-viz-sensitivity',
+        'visualizer.color_palette': 'basitune-viz-palette',
+        'visualizer.animation_speed': 'basitune-viz-speed',
+        'visualizer.glow_enabled': 'basitune-viz-glow',
+        'visualizer.glow_intensity': 'basitune-viz-glow-intensity',
+        'visualizer.bar_spacing': 'basitune-viz-bar-spacing',
+        'visualizer.particle_count': 'basitune-viz-particle-count',
+        'visualizer.line_thickness': 'basitune-viz-line-thickness'
+    };
+    
     async function saveSettings() {
         const openaiInput = document.getElementById('basitune-openai-key');
         const geniusInput = document.getElementById('basitune-genius-token');
         const closeToTrayCheckbox = document.getElementById('basitune-close-to-tray');
         const enableNotificationsCheckbox = document.getElementById('basitune-enable-notifications');
         const resumePlaybackCheckbox = document.getElementById('basitune-resume-playback');
+        const enableDiscordCheckbox = document.getElementById('basitune-enable-discord');
         const statusDiv = document.getElementById('basitune-settings-status');
         const saveBtn = document.getElementById('basitune-save-settings');
         
-        if (!openaiInput || !geniusInput || !closeToTrayCheckbox || !enableNotificationsCheckbox || !resumePlaybackCheckbox || !statusDiv || !saveBtn) {
+        if (!openaiInput || !geniusInput || !closeToTrayCheckbox || !enableNotificationsCheckbox || !resumePlaybackCheckbox || !enableDiscordCheckbox || !statusDiv || !saveBtn) {
             console.error('[Basitune] Settings elements not found');
             return;
         }
@@ -1788,6 +2173,7 @@
         const closeToTray = closeToTrayCheckbox.checked;
         const enableNotifications = enableNotificationsCheckbox.checked;
         const resumePlaybackOnStartup = resumePlaybackCheckbox.checked;
+        const enableDiscordPresence = enableDiscordCheckbox.checked;
         
         // Show saving status
         statusDiv.style.display = 'block';
@@ -1797,14 +2183,44 @@
         saveBtn.disabled = true;
         
         try {
-            await window.__TAURI__.core.invoke('save_config', {
-                openaiApiKey: openaiKey,
-                geniusAccessToken: geniusToken,
-                closeToTray: closeToTray,
-                enableNotifications: enableNotifications,
-                resumePlaybackOnStartup: resumePlaybackOnStartup
+            // Keys are stored encrypted by the backend; blank fields keep the saved ones
+            if (openaiKey) {
+                const status = await window.__TAURI__.core.invoke('set_api_secret', { name: 'openai_api_key', value: openaiKey });
+                showSecretStatus(openaiInput, status);
+            }
+            if (geniusToken) {
+                const status = await window.__TAURI__.core.invoke('set_api_secret', { name: 'genius_access_token', value: geniusToken });
+                showSecretStatus(geniusInput, status);
+            }
+            
+            // Only the fields in the patch are touched; blank AI fields go back to the defaults
+            await window.__TAURI__.core.invoke('update_config', {
+                patch: {
+                    general: {
+                        close_to_tray: closeToTray,
+                        enable_notifications: enableNotifications,
+                        resume_playback_on_startup: resumePlaybackOnStartup,
+                        enable_discord_presence: enableDiscordPresence
+                    },
+                    ai: {
+                        base_url: document.getElementById('basitune-ai-base-url')?.value.trim() || null,
+                        model: document.getElementById('basitune-ai-model')?.value.trim() || null,
+                        headers: parseHeaderLines(document.getElementById('basitune-ai-headers')?.value || ''),
+                        language: document.getElementById('basitune-ai-language')?.value.trim() || null,
+                        prompts: {
+                            artist_info: document.getElementById('basitune-ai-prompt-artist')?.value.trim() || null,
+                            song_context: document.getElementById('basitune-ai-prompt-song')?.value.trim() || null
+                        },
+                        budget: {
+                            daily_limit_usd: numberOrNull('basitune-ai-daily-limit'),
+                            monthly_limit_usd: numberOrNull('basitune-ai-monthly-limit')
+                        }
+                    }
+                }
             });
             
+            showFieldErrors(null, SETTINGS_TAB_INPUTS);
+            
             // Show success
             statusDiv.style.background = 'rgba(0, 255, 0, 0.2)';
             statusDiv.style.color = '#00ff00';
@@ -1820,7 +2236,8 @@
             // Show error
             statusDiv.style.background = 'rgba(255, 0, 0, 0.2)';
             statusDiv.style.color = '#ff6666';
-            statusDiv.textContent = '✗ Failed to save settings: ' + error;
+            statusDiv.textContent = '✗ Failed to save settings: ' + settingsErrorMessage(error);
+            showFieldErrors(error, SETTINGS_TAB_INPUTS);
         } finally {
             saveBtn.disabled = false;
         }
@@ -1927,10 +2344,8 @@
             }
         }
 
-        // Load saved settings
-        try {
-            const config = await window.__TAURI__.core.invoke('get_config');
-            
+        // Apply visualizer settings (saved config or a preset) to the visualizer and its controls
+        function applyVisualizerConfig(config) {
             // Basic settings
             const styleSelect = document.getElementById('basitune-viz-style');
             const colorInput = document.getElementById('basitune-viz-color');
@@ -1949,19 +2364,19 @@
             const particleCountInput = document.getElementById('basitune-viz-particle-count');
             const particlesValue = document.getElementById('basitune-viz-particles-value');
 
-            if (config.visualizer_style && styleSelect) {
-                styleSelect.value = config.visualizer_style;
-                window.basituneVisualizer.updateSettings({ style: config.visualizer_style });
-                updateControlVisibility(config.visualizer_style);
+            if (config.style && styleSelect) {
+                styleSelect.value = config.style;
+                window.basituneVisualizer.updateSettings({ style: config.style });
+                updateControlVisibility(config.style);
             }
-            if (config.visualizer_color && colorInput) {
-                colorInput.value = config.visualizer_color;
-                window.basituneVisualizer.updateSettings({ color: config.visualizer_color });
+            if (config.color && colorInput) {
+                colorInput.value = config.color;
+                window.basituneVisualizer.updateSettings({ color: config.color });
             }
-            if (config.visualizer_sensitivity !== undefined && sensitivityInput && sensitivityValue) {
-                sensitivityInput.value = config.visualizer_sensitivity;
-                sensitivityValue.textContent = config.visualizer_sensitivity.toFixed(1);
-                window.basituneVisualizer.updateSettings({ sensitivity: config.visualizer_sensitivity });
+            if (config.sensitivity !== undefined && sensitivityInput && sensitivityValue) {
+                sensitivityInput.value = config.sensitivity;
+                sensitivityValue.textContent = config.sensitivity.toFixed(1);
+                window.basituneVisualizer.updateSettings({ sensitivity: config.sensitivity });
             }
             if (config.color_palette && paletteSelect) {
                 paletteSelect.value = config.color_palette;
@@ -2009,10 +2424,17 @@
                     window.basituneVisualizer.updateSettings({ lineThickness: config.line_thickness });
                 }
             }
+        }
+
+        // Load saved settings
+        try {
+            applyVisualizerConfig((await window.__TAURI__.core.invoke('get_config')).visualizer);
         } catch (error) {
             console.error('[Basitune] Failed to load visualizer settings:', error);
         }
 
+        setupVisualizerPresets(applyVisualizerConfig);
+
         // Setup event listeners for visualizer controls
         const toggleBtn = document.getElementById('basitune-viz-toggle');
         const styleSelect = document.getElementById('basitune-viz-style');
@@ -2185,36 +2607,149 @@
         console.log('[Basitune] Visualizer initialized');
     }
     
+    // Visualizer presets: built-in ones plus the user's own, stored by the backend
+    async function loadVisualizerPresets(selected) {
+        const select = document.getElementById('basitune-viz-preset');
+        if (!select) return [];
+        
+        const presets = await window.__TAURI__.core.invoke('list_visualizer_presets');
+        select.innerHTML = '';
+        for (const [label, builtin] of [['Built-in', true], ['My Presets', false]]) {
+            const group = presets.filter(p => p.builtin === builtin);
+            if (group.length === 0) continue;
+            const optgroup = document.createElement('optgroup');
+            optgroup.label = label;
+            optgroup.style.cssText = 'background: #1a1a1a; color: rgba(255, 255, 255, 0.6);';
+            for (const preset of group) {
+                const option = document.createElement('option');
+                option.value = preset.name;
+                option.textContent = preset.name;
+                option.style.cssText = 'background: #1a1a1a !important; color: #fff !important;';
+                optgroup.appendChild(option);
+            }
+            select.appendChild(optgroup);
+        }
+        if (selected && presets.some(p => p.name === selected)) {
+            select.value = selected;
+        }
+        return presets;
+    }
+    
+    function setupVisualizerPresets(applyVisualizerConfig) {
+        const select = document.getElementById('basitune-viz-preset');
+        const nameInput = document.getElementById('basitune-viz-preset-name');
+        const status = document.getElementById('basitune-viz-preset-status');
+        const fileInput = document.getElementById('basitune-viz-preset-file');
+        if (!select || !nameInput || !status || !fileInput) return;
+        
+        const invoke = window.__TAURI__.core.invoke;
+        const showStatus = (message, isError = false) => {
+            status.textContent = message;
+            status.style.color = isError ? '#ff6b6b' : 'rgba(255, 255, 255, 0.6)';
+        };
+        // Run an action, reporting backend errors (e.g. a taken name) under the buttons
+        const run = (action) => async () => {
+            try {
+                await action();
+            } catch (error) {
+                showStatus(settingsErrorMessage(error), true);
+            }
+        };
+        
+        loadVisualizerPresets().catch(error => {
+            console.error('[Basitune] Failed to load visualizer presets:', error);
+        });
+        
+        select.onchange = () => {
+            nameInput.value = select.value;
+        };
+        
+        document.getElementById('basitune-viz-preset-apply').onclick = run(async () => {
+            if (!select.value) return;
+            const settings = await invoke('apply_visualizer_preset', { name: select.value });
+            applyVisualizerConfig(settings);
+            showStatus(`Applied "${select.value}"`);
+        });
+        
+        document.getElementById('basitune-viz-preset-save').onclick = run(async () => {
+            const name = await invoke('save_visualizer_preset', {
+                name: nameInput.value,
+                settings: collectVisualizerSettings()
+            });
+            await loadVisualizerPresets(name);
+            showStatus(`Saved "${name}"`);
+        });
+        
+        document.getElementById('basitune-viz-preset-rename').onclick = run(async () => {
+            const name = await invoke('rename_visualizer_preset', {
+                oldName: select.value,
+                newName: nameInput.value
+            });
+            await loadVisualizerPresets(name);
+            showStatus(`Renamed to "${name}"`);
+        });
+        
+        document.getElementById('basitune-viz-preset-delete').onclick = run(async () => {
+            const name = select.value;
+            await invoke('delete_visualizer_preset', { name });
+            await loadVisualizerPresets();
+            nameInput.value = '';
+            showStatus(`Deleted "${name}"`);
+        });
+        
+        document.getElementById('basitune-viz-preset-export').onclick = run(async () => {
+            const path = await invoke('export_visualizer_presets', {});
+            showStatus(`Exported to ${path}`);
+        });
+        
+        document.getElementById('basitune-viz-preset-import').onclick = () => fileInput.click();
+        fileInput.onchange = run(async () => {
+            const file = fileInput.files && fileInput.files[0];
+            fileInput.value = '';
+            if (!file) return;
+            const names = await invoke('import_visualizer_presets', { contents: await file.text() });
+            await loadVisualizerPresets(names[0]);
+            showStatus(`Imported ${names.length} preset${names.length === 1 ? '' : 's'}`);
+        });
+    }
+    
+    // Current visualizer settings, read from the controls
+    function collectVisualizerSettings() {
+        const styleSelect = document.getElementById('basitune-viz-style');
+        const colorInput = document.getElementById('basitune-viz-color');
+        const sensitivityInput = document.getElementById('basitune-viz-sensitivity');
+        const paletteSelect = document.getElementById('basitune-viz-palette');
+        const speedInput = document.getElementById('basitune-viz-speed');
+        const glowCheckbox = document.getElementById('basitune-viz-glow');
+        const glowIntensityInput = document.getElementById('basitune-viz-glow-intensity');
+        const barSpacingInput = document.getElementById('basitune-viz-bar-spacing');
+        const particleCountInput = document.getElementById('basitune-viz-particle-count');
+        const lineThicknessInput = document.getElementById('basitune-viz-line-thickness');
+
+        return {
+            style: styleSelect?.value || 'bars',
+            color: colorInput?.value || '#ff0000',
+            sensitivity: parseFloat(sensitivityInput?.value || '1.0'),
+            color_palette: paletteSelect?.value || 'single',
+            animation_speed: parseFloat(speedInput?.value || '1.0'),
+            glow_enabled: glowCheckbox?.checked || false,
+            glow_intensity: parseFloat(glowIntensityInput?.value || '10.0'),
+            bar_spacing: parseFloat(barSpacingInput?.value || '1.0'),
+            particle_count: parseInt(particleCountInput?.value || '80'),
+            line_thickness: parseFloat(lineThicknessInput?.value || '2.0')
+        };
+    }
+    
     // Save visualizer settings to backend
     async function saveVisualizerSettings() {
         try {
-            const styleSelect = document.getElementById('basitune-viz-style');
-            const colorInput = document.getElementById('basitune-viz-color');
-            const sensitivityInput = document.getElementById('basitune-viz-sensitivity');
-            const paletteSelect = document.getElementById('basitune-viz-palette');
-            const speedInput = document.getElementById('basitune-viz-speed');
-            const glowCheckbox = document.getElementById('basitune-viz-glow');
-            const glowIntensityInput = document.getElementById('basitune-viz-glow-intensity');
-            const barSpacingInput = document.getElementById('basitune-viz-bar-spacing');
-            const particleCountInput = document.getElementById('basitune-viz-particle-count');
-            const lineThicknessInput = document.getElementById('basitune-viz-line-thickness');
-
             await window.__TAURI__.core.invoke('save_visualizer_settings', {
-                settings: {
-                    style: styleSelect?.value || 'bars',
-                    color: colorInput?.value || '#ff0000',
-                    sensitivity: parseFloat(sensitivityInput?.value || '1.0'),
-                    color_palette: paletteSelect?.value || 'single',
-                    animation_speed: parseFloat(speedInput?.value || '1.0'),
-                    glow_enabled: glowCheckbox?.checked || false,
-                    glow_intensity: parseFloat(glowIntensityInput?.value || '10.0'),
-                    bar_spacing: parseFloat(barSpacingInput?.value || '1.0'),
-                    particle_count: parseInt(particleCountInput?.value || '80'),
-                    line_thickness: parseFloat(lineThicknessInput?.value || '2.0')
-                }
+                settings: collectVisualizerSettings()
             });
+            showFieldErrors(null, VISUALIZER_SETTING_INPUTS);
         } catch (error) {
-            console.error('[Basitune] Failed to save visualizer settings:', error);
+            console.error('[Basitune] Failed to save visualizer settings:', settingsErrorMessage(error));
+            showFieldErrors(error, VISUALIZER_SETTING_INPUTS);
         }
     }
     
@@ -2290,11 +2825,14 @@
     function getCurrentSongInfo() {
         const artistElement = document.querySelector('.byline.ytmusic-player-bar a');
         const titleElement = document.querySelector('.title.ytmusic-player-bar');
+        // Album links in the byline point at an MPREb browse page
+        const albumElement = document.querySelector('.byline.ytmusic-player-bar a[href*="browse/MPREb"]');
         
         if (artistElement && titleElement) {
             return {
                 artist: artistElement.textContent.trim(),
-                title: titleElement.textContent.trim()
+                title: titleElement.textContent.trim(),
+                album: albumElement ? albumElement.textContent.trim() : null
             };
         }
         
@@ -2339,6 +2877,132 @@
         return `<span id="${contentId}" class="basitune-truncated">${visibleText}</span><span class="basitune-read-more">Read more</span>`;
     }
     
+    // Streaming AI requests. Each feature ('artist', 'song') has at most one in flight;
+    // starting a new one cancels the previous, so a track change stops stale generations.
+    const AI_CANCELLED = 'AI request cancelled';
+    const aiChunkHandlers = new Map();
+    const activeAiRequests = {};
+    let aiRequestCounter = 0;
+    
+    // Called by the backend for each ai-chunk event
+    function handleAiChunk(chunk) {
+        const handler = aiChunkHandlers.get(chunk.request_id);
+        if (handler) {
+            handler(chunk);
+        }
+    }
+    
+    // Invoke an AI command with a request id so its text streams in; onText receives the
+    // text so far. Resolves to the full text, like a plain invoke.
+    async function invokeStreaming(feature, command, args, onText) {
+        if (activeAiRequests[feature]) {
+            window.__TAURI__.core.invoke('cancel_ai_request', { requestId: activeAiRequests[feature] }).catch(() => {});
+        }
+        
+        const requestId = `${feature}-${++aiRequestCounter}`;
+        activeAiRequests[feature] = requestId;
+        let text = '';
+        aiChunkHandlers.set(requestId, (chunk) => {
+            if (chunk.delta) {
+                text += chunk.delta;
+                onText(text);
+            }
+        });
+        
+        try {
+            return await window.__TAURI__.core.invoke(command, { ...args, requestId });
+        } finally {
+            aiChunkHandlers.delete(requestId);
+            if (activeAiRequests[feature] === requestId) {
+                delete activeAiRequests[feature];
+            }
+        }
+    }
+    
+    function isCancelled(error) {
+        return String(error?.message ?? error) === AI_CANCELLED;
+    }
+    
+    // Show partial text under a heading while a reply streams in
+    function renderStreamingText(container, headingTag, heading, text) {
+        let p = container.querySelector('p.basitune-streaming');
+        if (!p) {
+            setHTML(container, `<${headingTag}></${headingTag}><p class="basitune-streaming"></p>`);
+            setText(container.querySelector(headingTag), heading);
+            p = container.querySelector('p.basitune-streaming');
+        }
+        setText(p, text);
+    }
+    
+    function youTubeMusicSearchUrl(query) {
+        return `https://music.youtube.com/search?q=${encodeURIComponent(query)}`;
+    }
+    
+    // Search YouTube Music through its own search box, so playback isn't interrupted by a
+    // page load; navigate to the results page if the box can't be found
+    function searchYouTubeMusic(query) {
+        const input = document.querySelector('ytmusic-search-box input#input');
+        if (!input) {
+            window.location.href = youTubeMusicSearchUrl(query);
+            return;
+        }
+        input.focus();
+        input.value = query;
+        input.dispatchEvent(new Event('input', { bubbles: true }));
+        input.dispatchEvent(new KeyboardEvent('keydown', { key: 'Enter', code: 'Enter', keyCode: 13, bubbles: true }));
+    }
+    
+    // Add a chip per item that searches YouTube Music for it when clicked
+    function appendChips(container, items) {
+        for (const item of items) {
+            const chip = document.createElement('a');
+            chip.className = 'basitune-chip';
+            chip.href = youTubeMusicSearchUrl(item);
+            setText(chip, item);
+            chip.addEventListener('click', (e) => {
+                e.preventDefault();
+                searchYouTubeMusic(item);
+            });
+            container.appendChild(chip);
+        }
+    }
+    
+    // Render an artist profile from get_artist_profile. Structured profiles get a facts line,
+    // genre chips, members and similar-artist chips around the bio; a prose fallback (structured
+    // false) only has the bio.
+    function renderArtistProfile(bioDiv, artist, profile) {
+        const expandableBio = makeExpandable(bioDiv, profile.summary, 400);
+        // Use text nodes for content to avoid Trusted Types issues
+        setHTML(bioDiv, `
+            <h4></h4>
+            <div class="basitune-artist-facts"></div>
+            <div class="basitune-chips"></div>
+            <p></p>
+            <div class="basitune-artist-members"></div>
+            <div class="basitune-artist-similar"><div class="basitune-artist-label">Similar artists</div><div class="basitune-chips"></div></div>
+        `);
+        setText(bioDiv.querySelector('h4'), profile.structured ? profile.name : artist);
+        // expandableBio may contain HTML for read-more; use setHTML only for that part
+        setHTML(bioDiv.querySelector('p'), expandableBio);
+        
+        const facts = bioDiv.querySelector('.basitune-artist-facts');
+        const genres = bioDiv.querySelector(':scope > .basitune-chips');
+        const members = bioDiv.querySelector('.basitune-artist-members');
+        const similar = bioDiv.querySelector('.basitune-artist-similar');
+        
+        const factsLine = profile.structured ? [profile.origin, profile.years_active].filter(Boolean).join(' · ') : '';
+        setText(facts, factsLine);
+        appendChips(genres, profile.structured ? profile.genres : []);
+        setText(members, profile.structured && profile.members.length ? `Members: ${profile.members.join(', ')}` : '');
+        appendChips(similar.querySelector('.basitune-chips'), profile.structured ? profile.similar_artists : []);
+        
+        // Drop the parts this profile has nothing for
+        [facts, genres, members].filter((el) => !el.textContent).forEach((el) => el.remove());
+        if (!similar.querySelector('.basitune-chip')) {
+            similar.remove();
+        }
+    }
+    
     // Fetch artist info from AI via Tauri
     async function fetchArtistInfo(artist) {
         try {
@@ -2352,12 +3016,7 @@
             // Check if we have pre-loaded data for this artist
             if (preloadedArtistInfo && preloadedFromArtist === artist) {
                 console.log('[Basitune] Using pre-loaded artist info for:', artist);
-                const expandableBio = makeExpandable(bioDiv, preloadedArtistInfo, 400);
-                setHTML(bioDiv, `<h4></h4><p></p>`);
-                const h4 = bioDiv.querySelector('h4');
-                const p = bioDiv.querySelector('p');
-                setText(h4, artist);
-                setHTML(p, expandableBio);
+                renderArtistProfile(bioDiv, artist, preloadedArtistInfo);
                 // Clear pre-loaded data after use
                 preloadedArtistInfo = null;
                 preloadedFromArtist = null;
@@ -2373,31 +3032,144 @@
             
             console.log('[Basitune] Fetching AI info for:', artist);
             
-            // Call Tauri command - now returns plain text
-            const bio = await window.__TAURI__.core.invoke('get_artist_info', { artist });
+            // Call Tauri command - resolves to the artist profile. If the provider can't give a
+            // structured one, the prose bio streams into the sidebar instead.
+            const profile = await invokeStreaming('artist', 'get_artist_profile', { artist }, (text) => {
+                renderStreamingText(bioDiv, 'h4', artist, text);
+            });
             
-            console.log('[Basitune] Received AI bio');
+            console.log('[Basitune] Received AI artist profile; structured:', profile.structured);
             
-            // Display artist bio with read more functionality
-            const expandableBio = makeExpandable(bioDiv, bio, 400);
-            // Use text nodes for content to avoid Trusted Types issues
-            setHTML(bioDiv, `<h4></h4><p></p>`);
-            const h4 = bioDiv.querySelector('h4');
-            const p = bioDiv.querySelector('p');
-            setText(h4, artist);
-            // expandableBio may contain HTML for read-more; use setHTML only for that part
-            setHTML(p, expandableBio);
+            renderArtistProfile(bioDiv, artist, profile);
             
-            console.log('[Basitune] Artist bio rendered; length:', p?.textContent?.length || 0);
+            console.log('[Basitune] Artist bio rendered; length:', profile.summary.length);
             
             console.log('[Basitune] Loaded AI info for:', artist);
         } catch (error) {
+            if (isCancelled(error)) {
+                return;
+            }
             console.error('[Basitune] Error fetching artist info:', error);
             const bioDiv = document.getElementById('basitune-artist-bio');
             setHTML(bioDiv, `<p class="basitune-placeholder">Could not load artist information<br><small>${error}</small></p>`);
         }
     }
     
+    // Follow-up chat about the current track. Each track has its own conversation, kept by
+    // the backend; the track it belongs to is remembered so a late answer for the previous
+    // song isn't shown under the new one.
+    let chatTrack = null;
+    
+    function appendChatMessage(list, role, text) {
+        const message = document.createElement('div');
+        message.className = `basitune-chat-message basitune-chat-${role}`;
+        setText(message, text);
+        list.appendChild(message);
+        list.scrollTop = list.scrollHeight;
+        return message;
+    }
+    
+    async function loadSongChat(title, artist) {
+        const chatDiv = document.getElementById('basitune-song-chat');
+        if (!chatDiv || !window.__TAURI__?.core?.invoke) {
+            return;
+        }
+        
+        // A question about the previous track is no longer wanted
+        if (activeAiRequests.chat) {
+            window.__TAURI__.core.invoke('cancel_ai_request', { requestId: activeAiRequests.chat }).catch(() => {});
+        }
+        
+        chatTrack = { title, artist };
+        chatDiv.style.display = 'block';
+        const list = chatDiv.querySelector('.basitune-chat-messages');
+        setHTML(list, '');
+        setText(document.getElementById('basitune-chat-status'), '');
+        
+        try {
+            const conversation = await window.__TAURI__.core.invoke('get_song_chat', { title, artist });
+            if (chatTrack.title !== title || chatTrack.artist !== artist) {
+                return;
+            }
+            conversation.messages.forEach((turn) => appendChatMessage(list, turn.role, turn.content));
+        } catch (error) {
+            console.error('[Basitune] Error loading song chat:', error);
+        }
+    }
+    
+    async function askAboutSong() {
+        const input = document.getElementById('basitune-chat-input');
+        const status = document.getElementById('basitune-chat-status');
+        const list = document.querySelector('#basitune-song-chat .basitune-chat-messages');
+        const question = input.value.trim();
+        if (!question || !chatTrack) {
+            return;
+        }
+        
+        const { title, artist } = chatTrack;
+        input.value = '';
+        setText(status, '');
+        const asked = appendChatMessage(list, 'user', question);
+        const answer = appendChatMessage(list, 'assistant', '…');
+        
+        try {
+            const turn = await invokeStreaming('chat', 'chat_about_song', { title, artist, question }, (text) => {
+                setText(answer, text);
+                list.scrollTop = list.scrollHeight;
+            });
+            setText(answer, turn.content);
+        } catch (error) {
+            answer.remove();
+            if (isCancelled(error)) {
+                return;
+            }
+            // The backend only saves answered questions; give this one back to retry
+            asked.remove();
+            input.value = question;
+            console.error('[Basitune] Error asking about song:', error);
+            setText(status, `Could not get an answer: ${error}`);
+        }
+    }
+    
+    function setupSongChat() {
+        const input = document.getElementById('basitune-chat-input');
+        const status = document.getElementById('basitune-chat-status');
+        if (!input) {
+            return;
+        }
+        
+        document.getElementById('basitune-chat-send').addEventListener('click', askAboutSong);
+        // Enter asks; Shift+Enter starts a new line. Keys stay out of YouTube Music's shortcuts.
+        input.addEventListener('keydown', (e) => {
+            e.stopPropagation();
+            if (e.key === 'Enter' && !e.shiftKey) {
+                e.preventDefault();
+                askAboutSong();
+            }
+        });
+        
+        document.getElementById('basitune-chat-clear').addEventListener('click', async () => {
+            if (!chatTrack) return;
+            try {
+                await window.__TAURI__.core.invoke('clear_song_chat', chatTrack);
+                setHTML(document.querySelector('#basitune-song-chat .basitune-chat-messages'), '');
+                setText(status, 'Conversation cleared');
+            } catch (error) {
+                setText(status, `Could not clear the conversation: ${error}`);
+            }
+        });
+        
+        document.getElementById('basitune-chat-export').addEventListener('click', async () => {
+            if (!chatTrack) return;
+            try {
+                const path = await window.__TAURI__.core.invoke('export_song_chat', chatTrack);
+                setText(status, `Exported to ${path}`);
+            } catch (error) {
+                setText(status, String(error));
+            }
+        });
+    }
+    
     // Fetch song context from AI via Tauri
     async function fetchSongContext(title, artist) {
         try {
@@ -2452,7 +3224,11 @@
             
             // Store the promise to deduplicate concurrent requests
             lastSongContextKey = songKey;
-            pendingSongContext = window.__TAURI__.core.invoke('get_song_context', { title, artist });
+            const songInfo = getCurrentSongInfo();
+            const album = songInfo && songInfo.title === title ? songInfo.album : null;
+            pendingSongContext = invokeStreaming('song', 'get_song_context', { title, artist, album }, (text) => {
+                renderStreamingText(contextDiv, 'h5', `About "${title}"`, text);
+            });
             
             // Call Tauri command
             const context = await pendingSongContext;
@@ -2475,6 +3251,9 @@
             pendingSongContext = null;
             lastSongContextKey = null;
             
+            if (isCancelled(error)) {
+                return;
+            }
             console.error('[Basitune] Error fetching song context:', error);
             const contextDiv = document.getElementById('basitune-song-context');
             setHTML(contextDiv, `<p class="basitune-placeholder">Could not load song context<br><small>${error}</small></p>`);
@@ -2579,6 +3358,10 @@
             
             console.log('[Basitune] Loaded lyrics for:', title);
         } catch (error) {
+            // Lookups for a track that is no longer playing are cancelled by the backend
+            if (isCancelled(error)) {
+                return;
+            }
             console.error('[Basitune] Error fetching lyrics:', error);
             
             // Try to get search results
@@ -2596,6 +3379,27 @@
         }
     }
     
+    async function pinLyricsSource(result, title, artist) {
+        const lyricsDiv = document.getElementById('basitune-lyrics-content');
+        
+        setHTML(lyricsDiv, `
+            <div class="basitune-loading">
+                <div class="basitune-spinner"></div>
+                <div class="basitune-loading-text">Loading lyrics...</div>
+            </div>
+        `);
+        
+        try {
+            const lyrics = await window.__TAURI__.core.invoke('pin_lyrics_source', { title, artist, result });
+            setHTML(lyricsDiv, `<div id="basitune-lyrics-text" style="white-space: pre-wrap;"></div>`);
+            setText(document.getElementById('basitune-lyrics-text'), lyrics);
+            console.log('[Basitune] Pinned lyrics source for:', title, '->', result.url);
+        } catch (error) {
+            console.error('[Basitune] Failed to pin lyrics source:', error);
+            showLyricsError(error, title, artist);
+        }
+    }
+    
     function showLyricsSearchResults(results, originalTitle, originalArtist) {
         const lyricsDiv = document.getElementById('basitune-lyrics-content');
         
@@ -2617,9 +3421,10 @@
                 <div style="display: flex; flex-direction: column; gap: 8px;">
         `;
         
-        results.forEach(result => {
+        results.forEach((result, index) => {
             html += `
-                <div data-title="${result.title.replace(/"/g, '&quot;')}" 
+                <div data-index="${index}"
+                     data-title="${result.title.replace(/"/g, '&quot;')}" 
                      data-artist="${result.primary_artist.name.replace(/"/g, '&quot;')}"
                      class="basitune-suggestion-item"
                      style="
@@ -2696,11 +3501,11 @@
         setHTML(lyricsDiv, html);
         
         // Add click handlers for suggestion items
+        // Picking a suggestion pins that Genius page to the original track
         document.querySelectorAll('.basitune-suggestion-item').forEach(item => {
             item.addEventListener('click', () => {
-                const title = item.getAttribute('data-title');
-                const artist = item.getAttribute('data-artist');
-                fetchLyrics(title, artist);
+                const result = results[parseInt(item.getAttribute('data-index'), 10)];
+                pinLyricsSource(result, originalTitle, originalArtist);
             });
         });
         
@@ -2911,6 +3716,11 @@
         const observer = new MutationObserver(() => {
             const songInfo = getCurrentSongInfo();
             if (songInfo) {
+                // Let the backend cancel lookups still running for the previous track
+                if (songInfo.artist !== currentArtist || songInfo.title !== currentTitle) {
+                    setCurrentTrack(songInfo.title, songInfo.artist);
+                }
+                
                 // Update artist info if artist changed
                 if (songInfo.artist !== currentArtist) {
                     currentArtist = songInfo.artist;
@@ -2922,6 +3732,7 @@
                     currentTitle = songInfo.title;
                     fetchSongContext(currentTitle, currentArtist);
                     fetchLyrics(currentTitle, currentArtist);
+                    loadSongChat(currentTitle, currentArtist);
                     
                     // Update Discord Rich Presence
                     updateDiscordPresence(currentTitle, currentArtist);
@@ -2941,10 +3752,12 @@
         if (songInfo) {
             currentArtist = songInfo.artist;
             currentTitle = songInfo.title;
+            setCurrentTrack(currentTitle, currentArtist);
             // Fetch functions will check for pre-loaded data and use it instantly
             fetchArtistInfo(currentArtist);
             fetchSongContext(currentTitle, currentArtist);
             fetchLyrics(currentTitle, currentArtist);
+            loadSongChat(currentTitle, currentArtist);
             updateDiscordPresence(currentTitle, currentArtist);
         } else {
             console.debug('[Basitune] No initial song info found on startup');
@@ -2953,6 +3766,17 @@
         console.log('[Basitune] Song monitor started');
     }
     
+    // Tell the backend which track is playing. Artist info, song context and lyrics lookups for
+    // any other track are cancelled, and identical lookups share one request.
+    function setCurrentTrack(title, artist) {
+        if (!window.__TAURI__?.core?.invoke) {
+            return;
+        }
+        window.__TAURI__.core.invoke('set_current_track', { title, artist }).catch((error) => {
+            console.debug('[Basitune] Failed to set the current track:', error);
+        });
+    }
+    
     // Update Discord Rich Presence
     async function updateDiscordPresence(title, artist) {
         try {
@@ -2978,6 +3802,12 @@
             return;
         }
         
+        // Called by the backend when settings files change on disk
+        window.basituneConfigChanged = handleConfigChanged;
+        
+        // Called by the backend with streamed AI text
+        window.basituneAiChunk = handleAiChunk;
+        
         // Load saved state before creating sidebar
         try {
             const savedVisible = await window.__TAURI__.core.invoke('get_sidebar_visible');
@@ -3097,7 +3927,7 @@
                 
                 // Fetch all three content types from cache (returns instantly from DB)
                 const [artistInfo, songContext, lyrics] = await Promise.all([
-                    window.__TAURI__.core.invoke('get_artist_info', { artist: lastPlayback.artist }).catch(() => null),
+                    window.__TAURI__.core.invoke('get_artist_profile', { artist: lastPlayback.artist }).catch(() => null),
                     window.__TAURI__.core.invoke('get_song_context', { title: lastPlayback.title, artist: lastPlayback.artist }).catch(() => null),
                     window.__TAURI__.core.invoke('get_lyrics', { title: lastPlayback.title, artist: lastPlayback.artist }).catch(() => null)
                 ]);